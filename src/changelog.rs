@@ -1,53 +1,128 @@
+mod bump;
 mod change;
 mod changeset;
 mod contributor;
+mod issue;
+mod merge_request;
 mod release;
 
+pub use self::bump::Bump;
 pub use self::change::Change;
 pub(crate) use self::changeset::ChangeSet;
-pub(crate) use self::contributor::Contributor;
+pub(crate) use self::contributor::{Contribution, Contributor};
+pub(crate) use self::issue::Issue;
+pub(crate) use self::merge_request::MergeRequest;
 pub(crate) use self::release::Release;
-use crate::git::{Commit, Tag};
+use crate::config::Output;
+use crate::git::{Commit, Ignored, Mailmap, Tag};
 use crate::render;
 use crate::{Config, Error};
+use chrono::{DateTime, Utc};
+use semver::Version;
 use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// The name of the built-in change log template, which can be extended.
+const CHANGELOG_TEMPLATE: &str = "jilu/changelog.md";
+
+/// The name of the built-in release notes template, which can be extended.
+const RELEASE_NOTES_TEMPLATE: &str = "jilu/release-notes.md";
+
+/// The built-in change log formats, other than the default Markdown format,
+/// with the name of their template.
+pub const FORMATS: &[(&str, &str)] = &[("debian", "jilu/debian"), ("rpm", "jilu/rpm")];
+
+/// The part of the Git history covered by a change log.
+#[derive(Debug, Clone, Copy)]
+pub struct Range<'a> {
+    /// The commits of the change log, oldest first.
+    pub commits: &'a [Commit],
+
+    /// The commits preceding the change log, used to find the contributors
+    /// who contributed before its first release.
+    pub earlier: &'a [Commit],
+
+    /// The revision after which the change log starts (e.g. a tag), if any.
+    pub from: Option<&'a str>,
+
+    /// The revision at which the change log ends (e.g. `HEAD`).
+    pub to: &'a str,
+
+    /// The commit date of the `to` revision.
+    pub date: DateTime<Utc>,
+}
 
 #[derive(Debug, Serialize)]
 pub struct Changelog<'a> {
     config: &'a Config,
     unreleased: ChangeSet<'a>,
     releases: Vec<Release<'a>>,
+
+    /// The commit date of the revision at which the change log ends.
+    date: DateTime<Utc>,
+
+    /// The hand-written history of releases predating the generated change
+    /// log, rendered verbatim.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    legacy: Option<&'a str>,
+
+    /// The commits and tags left out of the change log, and why.
+    ignored: Vec<Ignored>,
 }
 
 impl<'a> Changelog<'a> {
-    pub fn new(config: &'a Config, commits: &'a [Commit], tags: Vec<Tag>) -> Result<Self, Error> {
+    /// Create a change log from the commits in the given range, and tags.
+    ///
+    /// Contributors are resolved to their canonical identity using the
+    /// `mailmap`, if any. The `ignored` commits and tags are extended with any
+    /// commits not included in the change log.
+    pub fn new(
+        config: &'a Config,
+        mailmap: Option<&'a Mailmap>,
+        range: Range<'a>,
+        tags: Vec<Tag>,
+        mut ignored: Vec<Ignored>,
+    ) -> Result<Self, Error> {
+        let commits = range.commits;
         let mut offset = 0;
+        let mut previous = range.from.map(str::to_owned);
+        let mut releases = Vec::with_capacity(tags.len());
+        let mut contributors = BTreeSet::new();
+
+        // Contributors to earlier commits are not new to the first release.
+        let mut earlier = ChangeSet::new(config, mailmap, None, String::new());
+        earlier.take_commits(0, range.earlier, None, &mut vec![])?;
+        earlier.find_new_contributors(&mut contributors);
 
-        let mut releases = tags
-            .into_iter()
-            .map(Release::new)
-            .collect::<Result<Vec<_>, _>>()?;
-
-        for release in &mut releases {
-            let mut changeset = ChangeSet::new(&config.contributor_footers);
-            offset = changeset.take_commits(
-                offset,
-                commits,
-                &config.accept_types,
-                Some(release.tag()),
-            )?;
-            release.with_changeset(changeset);
+        for tag in tags {
+            let from = previous.replace(tag.name.clone());
+            let mut changeset = ChangeSet::new(config, mailmap, from, tag.name.clone());
+            offset = changeset.take_commits(offset, commits, Some(&tag), &mut ignored)?;
+            changeset.find_new_contributors(&mut contributors);
+            releases.push(Release::new(tag, changeset));
         }
 
         releases.reverse();
 
-        let mut unreleased = ChangeSet::new(&config.contributor_footers);
-        unreleased.take_commits(offset, commits, &config.accept_types, None)?;
+        // Releases covered by the legacy history are still needed above to
+        // assign each commit to the correct release, but are not rendered.
+        let legacy = config.legacy.as_ref();
+        if let Some(legacy) = legacy {
+            releases.retain(|r| r.version() > &legacy.version);
+        }
+
+        let mut unreleased = ChangeSet::new(config, mailmap, previous, range.to.to_owned());
+        unreleased.take_commits(offset, commits, None, &mut ignored)?;
+        unreleased.find_new_contributors(&mut contributors);
 
         Ok(Self {
             config,
             releases,
+            date: range.date,
             unreleased,
+            legacy: legacy.and_then(|legacy| legacy.history.as_deref()),
+            ignored,
         })
     }
 
@@ -56,35 +131,458 @@ impl<'a> Changelog<'a> {
         self.releases.iter()
     }
 
+    /// Get the commits and tags left out of the change log.
+    pub fn ignored(&self) -> &[Ignored] {
+        &self.ignored
+    }
+
     /// Get the unreleased changes.
     pub fn unreleased(&self) -> &ChangeSet<'a> {
         &self.unreleased
     }
 
+    /// Calculate the version of the next release.
+    ///
+    /// The version is calculated by applying a bump to the latest released
+    /// version, the latest version of the legacy history, or `0.0.0` if there
+    /// are no releases yet. If no bump is provided, the bump is determined by
+    /// the unreleased changes, based on the configured `type_bumps`.
+    ///
+    /// # Errors
+    ///
+    /// If no bump is provided, and none of the unreleased changes require a
+    /// version bump, an error is returned.
+    pub fn next_version(&self, bump: Option<Bump>) -> Result<Version, Error> {
+        let bump = match bump {
+            Some(bump) => bump,
+            None => self
+                .unreleased
+                .bump(&self.config.type_bumps)
+                .ok_or("No unreleased changes require a version bump")?,
+        };
+
+        let latest = self
+            .releases
+            .first()
+            .map(|r| r.version().clone())
+            .or_else(|| self.config.legacy.as_ref().map(|l| l.version.clone()))
+            .unwrap_or_else(|| Version::new(0, 0, 0));
+
+        Ok(bump.apply(&latest))
+    }
+
+    /// Render the change log.
+    ///
+    /// This uses the configured template file, the `Template(...)` block of
+    /// the change log, or the built-in template, in that order.
     pub fn render(&self, include_metadata: bool) -> Result<String, Error> {
         let context = tera::Context::from_serialize(self)?;
-        let mut tera = tera::Tera::default();
-        let template = self
-            .config
-            .template
-            .as_deref()
-            .unwrap_or(include_str!("../template.md"));
+        let mut tera = self.tera()?;
+        let name = match (&self.config.template_path, &self.config.template) {
+            (Some(path), _) => {
+                let name = self.template_name(path);
+                tera.add_template_file(path, Some(&name))?;
+                name
+            }
+            (None, Some(template)) => {
+                tera.add_raw_template("template", template)?;
+                "template".to_owned()
+            }
+            (None, None) => CHANGELOG_TEMPLATE.to_owned(),
+        };
+
+        let mut log = tera.render(&name, &context)?;
+        if include_metadata {
+            if let Some(metadata) = &self.config.metadata {
+                log.push_str(&format!("\n\n{}\n", metadata));
+            }
+        }
+
+        Ok(log)
+    }
+
+    /// Get a single release, or the unreleased changes if no version is
+    /// provided.
+    ///
+    /// The unreleased changes are returned as a release without a version,
+    /// containing only the `changeset` field.
+    ///
+    /// # Errors
+    ///
+    /// If no release exists for the given version, an error is returned.
+    pub fn release(&self, version: Option<&Version>) -> Result<serde_json::Value, Error> {
+        match version {
+            None => Ok(serde_json::json!({ "changeset": self.unreleased })),
+            Some(version) => self
+                .releases
+                .iter()
+                .find(|r| r.version() == version)
+                .ok_or_else(|| format!("Unknown release: {}", version))
+                .map_err(Into::into)
+                .and_then(|r| serde_json::to_value(r).map_err(Into::into)),
+        }
+    }
+
+    /// Render the notes of a single release, or the unreleased changes if no
+    /// version is provided.
+    ///
+    /// This uses the release notes template, which has access to the
+    /// `config` and the selected `release`.
+    pub fn render_release(&self, version: Option<&Version>) -> Result<String, Error> {
+        let context = tera::Context::from_serialize(serde_json::json!({
+            "config": self.config,
+            "release": self.release(version)?,
+        }))?;
+        let mut tera = self.tera()?;
+        let name = match &self.config.release_notes_template {
+            Some(template) => {
+                tera.add_raw_template("template", template)?;
+                "template"
+            }
+            None => RELEASE_NOTES_TEMPLATE,
+        };
+
+        Ok(tera.render(name, &context)?)
+    }
+
+    /// Render an additional output file of the change log.
+    pub fn render_output(&self, output: &Output) -> Result<String, Error> {
+        self.render_template(&output.template, output.release.as_deref())
+    }
 
+    /// Render the change log using the given template, which is either the
+    /// name of a built-in template, a file in the template directory, or the
+    /// path to a template file.
+    ///
+    /// If a release is provided (a version, `latest` or `unreleased`), the
+    /// template has access to the `config` and the selected `release`, similar
+    /// to the release notes template. Otherwise the whole change log is
+    /// available.
+    ///
+    /// # Errors
+    ///
+    /// If the template cannot be found, or the release does not exist, an
+    /// error is returned.
+    pub fn render_template(&self, template: &str, release: Option<&str>) -> Result<String, Error> {
+        let mut tera = self.tera()?;
+        let name = if tera.get_template_names().any(|n| n == template) {
+            template.to_owned()
+        } else {
+            let path = Path::new(template);
+            let name = self.template_name(path);
+            tera.add_template_file(path, Some(&name))?;
+            name
+        };
+
+        let context = match release {
+            None => tera::Context::from_serialize(self)?,
+            Some(release) => {
+                let version = match release {
+                    "unreleased" => None,
+                    "latest" => Some(
+                        self.releases
+                            .first()
+                            .map(|r| r.version().clone())
+                            .ok_or("No releases found")?,
+                    ),
+                    v => Some(Version::parse(v.strip_prefix('v').unwrap_or(v))?),
+                };
+
+                tera::Context::from_serialize(serde_json::json!({
+                    "config": self.config,
+                    "release": self.release(version.as_ref())?,
+                }))?
+            }
+        };
+
+        Ok(tera.render(&name, &context)?)
+    }
+
+    /// Create a templating engine with the built-in templates, the files in
+    /// the configured template directory, and all custom filters registered.
+    fn tera(&self) -> Result<tera::Tera, Error> {
+        let mut tera = tera::Tera::default();
         let type_header = render::TypeHeader(self.config.type_headers.clone());
         let scope_header = render::ScopeHeader(self.config.scope_headers.clone());
 
-        tera.add_raw_template("template", template)?;
+        tera.add_raw_templates([
+            (CHANGELOG_TEMPLATE, include_str!("../template.md")),
+            (RELEASE_NOTES_TEMPLATE, include_str!("../release-notes.md")),
+            ("jilu/debian", include_str!("../debian.txt")),
+            ("jilu/rpm", include_str!("../rpm.txt")),
+            ("jilu/atom", include_str!("../atom.xml")),
+            ("jilu/rss", include_str!("../rss.xml")),
+        ])?;
+        if let Some(dir) = &self.config.template_dir {
+            tera.add_template_files(render::template_files(dir)?)?;
+        }
+
         tera.register_filter("indent", render::indent);
+        tera.register_filter("markdown", render::markdown);
         tera.register_filter("typeheader", type_header);
         tera.register_filter("scopeheader", scope_header);
 
-        let mut log = tera.render("template", &context)?;
-        if include_metadata {
-            if let Some(metadata) = &self.config.metadata {
-                log.push_str(&format!("\n\n{}\n", metadata));
+        Ok(tera)
+    }
+
+    /// The name of a template file, relative to the template directory if the
+    /// file is part of it.
+    fn template_name(&self, path: &Path) -> String {
+        let relative = (self.config.template_dir.as_deref())
+            .and_then(|dir| path.strip_prefix(dir).ok())
+            .or_else(|| path.file_name().map(Path::new));
+
+        render::template_name(relative.unwrap_or(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Legacy;
+    use chrono::{TimeZone as _, Utc};
+
+    #[test]
+    fn test_legacy_releases() {
+        let commits = [
+            Commit::fixture("one", "feat: one"),
+            Commit::fixture("two", "feat: two"),
+            Commit::fixture("three", "feat: three"),
+        ];
+        let tags = [("0.1.0", 0), ("0.2.0", 1), ("1.0.0", 2)]
+            .map(|(version, idx)| Tag {
+                message: None,
+                name: format!("v{}", version),
+                version: Version::parse(version).unwrap(),
+                tagger: None,
+                commit: commits[idx].clone(),
+            })
+            .to_vec();
+
+        #[rustfmt::skip]
+        let cases = [
+            (None, vec!["1.0.0", "0.2.0", "0.1.0"]),
+            (Some("0.1.0"), vec!["1.0.0", "0.2.0"]),
+            (Some("0.1.5"), vec!["1.0.0", "0.2.0"]),
+            (Some("0.2.0"), vec!["1.0.0"]),
+            (Some("1.0.0"), vec![]),
+        ];
+
+        for (legacy, expected) in cases {
+            let config = Config {
+                legacy: legacy.map(|version| Legacy {
+                    version: Version::parse(version).unwrap(),
+                    file: None,
+                    history: Some("## 0.1.0".to_owned()),
+                }),
+                ..Config::default()
+            };
+
+            let range = Range {
+                commits: &commits,
+                earlier: &[],
+                from: None,
+                to: "HEAD",
+                date: Utc.timestamp_opt(0, 0).unwrap(),
+            };
+            let log = Changelog::new(&config, None, range, tags.clone(), vec![]).unwrap();
+            let releases = log
+                .releases()
+                .map(|r| r.version().to_string())
+                .collect::<Vec<_>>();
+
+            assert_eq!(releases, expected, "{:?}", legacy);
+            assert_eq!(log.legacy, legacy.map(|_| "## 0.1.0"), "{:?}", legacy);
+            assert_eq!(log.unreleased.changes().len(), 0, "{:?}", legacy);
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        let config = Config {
+            forge: Some(crate::forge::Forge::github("owner/repo".to_owned())),
+            ..Config::default()
+        };
+        let earlier = [Commit::fixture("one", "feat: one")];
+        let commits = [
+            Commit::fixture("two", "feat: two"),
+            Commit::fixture("three", "feat: three"),
+        ];
+        let tags = vec![Tag {
+            message: None,
+            name: "v0.2.0".to_owned(),
+            version: Version::new(0, 2, 0),
+            tagger: None,
+            commit: commits[0].clone(),
+        }];
+
+        #[rustfmt::skip]
+        let cases = [
+            (None, &earlier[..0], "HEAD", "commits", "compare/v0.2.0...HEAD", 1),
+            (Some("v0.1.0"), &earlier[..], "three", "compare/v0.1.0...v0.2.0", "compare/v0.2.0...three", 0),
+        ];
+
+        for (from, earlier, to, release_url, unreleased_url, new_contributors) in cases {
+            let range = Range {
+                commits: &commits,
+                earlier,
+                from,
+                to,
+                date: Utc.timestamp_opt(0, 0).unwrap(),
+            };
+            let log = Changelog::new(&config, None, range, tags.clone(), vec![]).unwrap();
+            let release = log.releases().next().unwrap().changeset();
+            let url = |path: &str| Some(format!("https://github.com/owner/repo/{}", path));
+
+            assert_eq!(release.compare_url(), url(release_url), "{:?}", from);
+            assert_eq!(
+                log.unreleased().compare_url(),
+                url(unreleased_url),
+                "{:?}",
+                from
+            );
+            assert_eq!(
+                release.new_contributors().len(),
+                new_contributors,
+                "{:?}",
+                from
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_feeds() {
+        let mut config = Config {
+            forge: Some(crate::forge::Forge::github("owner/repo".to_owned())),
+            ..Config::default()
+        };
+        config.feed.title = Some("Fish & Chips".to_owned());
+        config.feed.id = Some("https://github.com/owner/repo".to_owned());
+
+        let commits = [Commit::fixture("one", "feat: one")];
+        let tag = Tag {
+            message: Some("Crispy & <hot>\n\n**Notes** & more\n".to_owned()),
+            name: "v0.2.0".to_owned(),
+            version: Version::new(0, 2, 0),
+            tagger: Some(commits[0].author.clone()),
+            commit: commits[0].clone(),
+        };
+        let range = Range {
+            commits: &commits,
+            earlier: &[],
+            from: None,
+            to: "HEAD",
+            date: Utc.timestamp_opt(86400, 0).unwrap(),
+        };
+
+        #[rustfmt::skip]
+        let cases = [
+            ("jilu/atom", vec![], vec![
+                "<title>Fish &amp; Chips</title>",
+                "<updated>1970-01-02T00:00:00+00:00</updated>",
+            ]),
+            ("jilu/atom", vec![tag.clone()], vec![
+                "<title>Fish &amp; Chips</title>",
+                "<updated>1970-01-01T00:00:00+00:00</updated>",
+                "<title>0.2.0: Crispy &amp; &lt;hot&gt;</title>",
+                "<link href=\"https://github.com/owner/repo/releases/tag/v0.2.0\"/>",
+                "<email>jane@example.com</email>",
+                "&lt;p&gt;&lt;strong&gt;Notes&lt;/strong&gt; &amp;amp; more&lt;/p&gt;",
+            ]),
+            ("jilu/rss", vec![], vec![
+                "<lastBuildDate>Fri, 02 Jan 1970 00:00:00 +0000</lastBuildDate>",
+            ]),
+            ("jilu/rss", vec![tag.clone()], vec![
+                "<title>Fish &amp; Chips</title>",
+                "<lastBuildDate>Thu, 01 Jan 1970 00:00:00 +0000</lastBuildDate>",
+                "<link>https://github.com/owner/repo/releases/tag/v0.2.0</link>",
+                "<author>jane@example.com (Jane Doe)</author>",
+                "&lt;p&gt;&lt;strong&gt;Notes&lt;/strong&gt; &amp;amp; more&lt;/p&gt;",
+            ]),
+        ];
+
+        for (template, tags, expected) in cases {
+            let log = Changelog::new(&config, None, range, tags, vec![]).unwrap();
+            let feed = log.render_template(template, None).unwrap();
+
+            for expected in expected {
+                assert!(
+                    feed.contains(expected),
+                    "{}: {}\n{}",
+                    template,
+                    expected,
+                    feed
+                );
             }
+            assert!(!feed.contains("&#x2F;"), "{}\n{}", template, feed);
         }
+    }
 
-        Ok(log)
+    #[test]
+    fn test_render_formats() {
+        let mut config = Config::default();
+        config.package.name = Some("jilu".to_owned());
+        config.package.maintainer = Some("Packager <packager@example.com>".to_owned());
+
+        let commits = [
+            Commit::fixture("one", "feat(cli): 50% faster output"),
+            Commit::fixture("two", "fix: typo"),
+        ];
+
+        let mut tagger = commits[0].author.clone();
+        tagger.time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let tags = vec![
+            Tag {
+                message: Some("100% done\n".to_owned()),
+                name: "v1.0.0-rc.1".to_owned(),
+                version: Version::parse("1.0.0-rc.1").unwrap(),
+                tagger: Some(tagger),
+                commit: commits[0].clone(),
+            },
+            Tag {
+                message: None,
+                name: "v1.0.0".to_owned(),
+                version: Version::new(1, 0, 0),
+                tagger: None,
+                commit: commits[1].clone(),
+            },
+        ];
+        let range = Range {
+            commits: &commits,
+            earlier: &[],
+            from: None,
+            to: "HEAD",
+            date: Utc.timestamp_opt(0, 0).unwrap(),
+        };
+        let log = Changelog::new(&config, None, range, tags, vec![]).unwrap();
+
+        assert_eq!(
+            log.render_template("jilu/debian", None).unwrap(),
+            concat!(
+                "jilu (1.0.0-1) unstable; urgency=medium\n",
+                "\n",
+                "  * fix: typo\n",
+                "\n",
+                " -- Packager <packager@example.com>  Thu, 01 Jan 1970 00:00:00 +0000\n",
+                "\n",
+                "jilu (1.0.0~rc.1-1) unstable; urgency=medium\n",
+                "\n",
+                "  * 100% done\n",
+                "  * feat(cli): 50% faster output\n",
+                "\n",
+                " -- Jane Doe <jane@example.com>  Tue, 14 Nov 2023 22:13:20 +0000\n",
+            )
+        );
+        assert_eq!(
+            log.render_template("jilu/rpm", None).unwrap(),
+            concat!(
+                "* Thu Jan 01 1970 Packager <packager@example.com> - 1.0.0-1\n",
+                "- fix: typo\n",
+                "\n",
+                "* Tue Nov 14 2023 Jane Doe <jane@example.com> - 1.0.0~rc.1-1\n",
+                "- 100%% done\n",
+                "- feat(cli): 50%% faster output\n",
+            )
+        );
     }
 }
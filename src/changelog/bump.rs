@@ -0,0 +1,86 @@
+use crate::Error;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// The kind of version bump a change requires.
+///
+/// The variants are ordered from least to most significant, so the required
+/// bump for a set of changes is the maximum of all individual bumps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// Apply the bump to the given version.
+    ///
+    /// This follows the SemVer rules for initial development (`0.y.z`), where
+    /// breaking changes bump the minor version, and any other change bumps the
+    /// patch version.
+    ///
+    /// A pre-release version is promoted to its regular release version,
+    /// regardless of the bump.
+    pub fn apply(self, version: &Version) -> Version {
+        let mut next = Version::new(version.major, version.minor, version.patch);
+        if !version.pre.is_empty() {
+            return next;
+        }
+
+        match (self, version.major) {
+            (Bump::Major, 0) | (Bump::Minor, 1..) => {
+                next.minor += 1;
+                next.patch = 0;
+            }
+            (Bump::Major, _) => {
+                next.major += 1;
+                next.minor = 0;
+                next.patch = 0;
+            }
+            (Bump::Minor, 0) | (Bump::Patch, _) => next.patch += 1,
+        }
+
+        next
+    }
+}
+
+impl FromStr for Bump {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "major" => Ok(Bump::Major),
+            "minor" => Ok(Bump::Minor),
+            "patch" => Ok(Bump::Patch),
+            _ => Err(format!("unknown version bump: {}", s).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        #[rustfmt::skip]
+        let cases = [
+            (Bump::Major, "1.2.3", "2.0.0"),
+            (Bump::Minor, "1.2.3", "1.3.0"),
+            (Bump::Patch, "1.2.3", "1.2.4"),
+            (Bump::Major, "0.2.3", "0.3.0"),
+            (Bump::Minor, "0.2.3", "0.2.4"),
+            (Bump::Patch, "0.2.3", "0.2.4"),
+            (Bump::Major, "2.0.0-rc.1", "2.0.0"),
+            (Bump::Patch, "0.1.0-alpha", "0.1.0"),
+        ];
+
+        for (bump, version, expected) in cases {
+            let version = Version::parse(version).unwrap();
+            assert_eq!(bump.apply(&version).to_string(), expected);
+        }
+    }
+}
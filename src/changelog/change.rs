@@ -1,6 +1,7 @@
-use crate::changelog::Contributor;
-use crate::git::Commit;
-use crate::Error;
+use crate::changelog::merge_request::{GithubMergeCommit, MergeRequest, Platform};
+use crate::changelog::{Contributor, Issue};
+use crate::git::{Commit, Mailmap};
+use crate::{Config, Error};
 use conventional::{Commit as CCommit, Simple as _};
 use serde::ser::{SerializeStruct, Serializer};
 use serde::Serialize;
@@ -13,18 +14,79 @@ use tera::Value;
 pub struct Change<'a> {
     commit: &'a Commit,
     conventional: CCommit<'a>,
+    config: &'a Config,
+
+    /// The mailmap used to resolve the canonical identity of contributors.
+    mailmap: Option<&'a Mailmap>,
+
+    /// The merge request referenced before the conventional commit message,
+    /// if any.
+    prefix_merge_request: Option<(Platform, usize)>,
+
+    /// The changes merged into the history by this change, if any.
+    merged: Vec<Change<'a>>,
 }
 
 impl<'a> Change<'a> {
-    pub(crate) fn new(commit: &'a Commit) -> Result<Self, Error> {
-        let conventional = CCommit::new(&commit.message)?;
+    pub(crate) fn new(
+        commit: &'a Commit,
+        config: &'a Config,
+        mailmap: Option<&'a Mailmap>,
+    ) -> Result<Self, Error> {
+        let (message, prefix_merge_request) = MergeRequest::strip_prefix(&commit.message);
+        let conventional = CCommit::new(message)?;
 
         Ok(Self {
             commit,
             conventional,
+            config,
+            mailmap,
+            prefix_merge_request,
+            merged: Vec::new(),
         })
     }
 
+    /// The changes of all conventional commits merged by the given commit,
+    /// ordered from oldest to newest, and the merged commits that failed to
+    /// parse.
+    pub(crate) fn from_merged(
+        commit: &'a Commit,
+        config: &'a Config,
+        mailmap: Option<&'a Mailmap>,
+    ) -> (Vec<Self>, Vec<(&'a Commit, Error)>) {
+        let mut changes = vec![];
+        let mut failed = vec![];
+        for commit in &commit.merged {
+            match Change::new(commit, config, mailmap) {
+                Ok(change) => changes.push(change),
+                Err(err) => failed.push((commit, err)),
+            }
+        }
+
+        (changes, failed)
+    }
+
+    /// Set the changes merged into the history by this change, ordered from
+    /// oldest to newest.
+    pub(crate) fn set_merged(&mut self, merged: Vec<Change<'a>>) {
+        // Similar to the changes in a change set, merged changes are ordered
+        // from newest to oldest.
+        self.merged = merged.into_iter().rev().collect();
+    }
+
+    /// Only keep the merged changes for which the predicate returns `true`.
+    pub(crate) fn retain_merged(&mut self, f: impl FnMut(&Change<'_>) -> bool) {
+        self.merged.retain(f);
+    }
+
+    /// The changes merged into the history by this change.
+    ///
+    /// This is only populated for merge commits, when the history is walked
+    /// using [`HistoryMode::MergeExpanded`](crate::config::HistoryMode).
+    pub(crate) fn merged(&self) -> &[Change<'a>] {
+        &self.merged
+    }
+
     /// The type of the change.
     pub(crate) fn type_(&self) -> &str {
         self.conventional.type_()
@@ -40,12 +102,50 @@ impl<'a> Change<'a> {
         self.conventional.description()
     }
 
+    /// Whether the change introduces a breaking change.
+    ///
+    /// This is the case if the type or scope is followed by a `!`, or if the
+    /// commit has a `BREAKING CHANGE` footer.
+    pub(crate) fn breaking(&self) -> bool {
+        self.conventional.breaking()
+    }
+
+    /// The description of the breaking change, if any.
+    ///
+    /// This is the value of the `BREAKING CHANGE` footer, or the description
+    /// of the change if it is marked as breaking using `!`.
+    pub(crate) fn breaking_description(&self) -> Option<String> {
+        if !self.breaking() {
+            return None;
+        }
+
+        self.conventional
+            .footers()
+            .iter()
+            .find(|f| matches!(f.token(), "BREAKING CHANGE" | "BREAKING-CHANGE"))
+            .map(|f| f.value())
+            .or_else(|| Some(self.description()))
+            .map(str::to_owned)
+    }
+
     /// The description of a Github merge commit, including the PR number, if
     /// any.
     pub(crate) fn merge_commit_description(&self) -> Option<GithubMergeCommit<'_>> {
         GithubMergeCommit::new(self.description())
     }
 
+    /// The merge request referenced by this change, if any.
+    pub(crate) fn merge_request(&self) -> Option<MergeRequest<'_>> {
+        match self.prefix_merge_request {
+            Some((platform, number)) => Some(MergeRequest {
+                description: self.description(),
+                number,
+                platform,
+            }),
+            None => MergeRequest::detect(self.description(), &self.commit.message),
+        }
+    }
+
     /// The body of the change.
     pub(crate) fn body(&self) -> Option<&str> {
         self.conventional.body()
@@ -64,36 +164,103 @@ impl<'a> Change<'a> {
         &self.commit.id
     }
 
+    /// The links to this change on the forge, if any.
+    pub(crate) fn urls(&self) -> HashMap<&'static str, String> {
+        let forge = match &self.config.forge {
+            Some(forge) => forge,
+            None => return HashMap::new(),
+        };
+
+        let mut urls = HashMap::new();
+        if let Some(url) = forge.commit_url(self.id(), self.short_id()) {
+            urls.insert("commit", url);
+        }
+        if let Some(url) = self
+            .merge_request()
+            .and_then(|mr| forge.pull_request_url(&mr.number.to_string()))
+        {
+            urls.insert("pull_request", url);
+        }
+
+        urls
+    }
+
+    /// The issues referenced in the footers of this change (e.g. `Closes #12`).
+    pub(crate) fn issues(&self) -> Vec<Issue> {
+        let issues = &self.config.issues;
+
+        self.conventional
+            .footers()
+            .iter()
+            .filter(|f| issues.footers.contains(&f.token().to_ascii_lowercase()))
+            .flat_map(|f| {
+                // The `#` of a `Closes #12` footer is part of the separator.
+                let value = match f.separator() {
+                    " #" => format!("#{}", f.value()),
+                    _ => f.value().to_owned(),
+                };
+
+                Issue::parse_all(&value, &issues.trackers, self.config.forge.as_ref())
+            })
+            .collect()
+    }
+
     /// The author details of this change.
     pub(crate) fn author(&self) -> Contributor {
-        (
-            self.commit.author.name.as_str(),
-            self.commit.author.email.as_str(),
-        )
-            .into()
+        self.contributor(&self.commit.author.name, &self.commit.author.email)
     }
 
     /// The committer details of this change.
     pub(crate) fn committer(&self) -> Contributor {
-        (
-            self.commit.committer.name.as_str(),
-            self.commit.committer.email.as_str(),
-        )
-            .into()
+        self.contributor(&self.commit.committer.name, &self.commit.committer.email)
+    }
+
+    /// The contributor with the given name and email, resolved to their
+    /// canonical identity using the mailmap, if any.
+    fn contributor(&self, name: &str, email: &str) -> Contributor {
+        Contributor::resolve(name, email, self.mailmap)
+    }
+
+    /// The contributors to this change, excluding merged changes, with the
+    /// role in which they contributed.
+    ///
+    /// The role is either `author`, `committer`, or the lowercase token of the
+    /// footer listing the contributor (e.g. `co-authored-by`).
+    pub(crate) fn roles(&self, contributor_footers: &[String]) -> Vec<(Contributor, String)> {
+        let mut roles: Vec<_> = self
+            .conventional
+            .footers()
+            .iter()
+            .map(|f| (f.token().to_ascii_lowercase(), f.value()))
+            .filter(|(token, _)| contributor_footers.contains(token))
+            .filter_map(|(token, value)| Some((parse_contributor_footer(value)?, token)))
+            .map(|(c, token)| (self.contributor(&c.name, &c.email), token))
+            .chain([
+                (self.author(), "author".to_owned()),
+                (self.committer(), "committer".to_owned()),
+            ])
+            .collect();
+
+        roles.sort_unstable();
+        roles.dedup();
+        roles
     }
 
     /// The list of contributors for this change.
     ///
     /// This includes the author and committer of the change, as well as any
-    /// contributors listed in the footers of the commit.
+    /// contributors listed in the footers of the commit, and the contributors
+    /// of any merged changes.
     pub(crate) fn contributors(&self, contributor_footers: &[String]) -> Vec<Contributor> {
         let mut contributors: Vec<_> = self
-            .conventional
-            .footers()
-            .iter()
-            .filter(|f| contributor_footers.contains(&f.token().to_ascii_lowercase()))
-            .filter_map(|f| parse_contributor_footer(f.value()))
-            .chain([self.author(), self.committer()])
+            .roles(contributor_footers)
+            .into_iter()
+            .map(|(contributor, _)| contributor)
+            .chain(
+                self.merged
+                    .iter()
+                    .flat_map(|c| c.contributors(contributor_footers)),
+            )
             .collect();
 
         contributors.sort_unstable();
@@ -130,9 +297,13 @@ impl Serialize for Change<'_> {
     where
         S: Serializer,
     {
-        let mut count = 5;
+        let mut count = 8;
 
         let scope = self.scope().inspect(|_| count += 1);
+        let breaking_description = self.breaking_description().inspect(|_| count += 1);
+        let merged = Some(self.merged())
+            .filter(|m| !m.is_empty())
+            .inspect(|_| count += 1);
         let body = self.body().inspect(|_| count += 1);
         let commit = HashMap::from([("id", self.id()), ("short_id", self.short_id())]);
         let merge_commit = self.merge_commit_description().map(|c| {
@@ -143,119 +314,38 @@ impl Serialize for Change<'_> {
             ])
         });
 
+        let merge_request = self.merge_request().inspect(|_| count += 1);
+
         let mut state = serializer.serialize_struct("Change", count)?;
         state.serialize_field("type", self.type_())?;
         state.serialize_field("description", self.description())?;
         state.serialize_field("commit", &commit)?;
         state.serialize_field("author", &self.author())?;
         state.serialize_field("committer", &self.committer())?;
+        state.serialize_field("breaking", &self.breaking())?;
+        state.serialize_field("urls", &self.urls())?;
+        state.serialize_field("issues", &self.issues())?;
 
         if let Some(scope) = scope {
             state.serialize_field("scope", &scope)?;
         }
+        if let Some(breaking_description) = breaking_description {
+            state.serialize_field("breaking_description", &breaking_description)?;
+        }
         if let Some(merge_commit) = merge_commit {
             state.serialize_field("merge_commit_description", &merge_commit)?;
         }
+        if let Some(merge_request) = merge_request {
+            state.serialize_field("merge_request", &merge_request)?;
+        }
         if let Some(body) = body {
             state.serialize_field("body", &body)?;
         }
-
-        state.end()
-    }
-}
-
-pub(crate) struct GithubMergeCommit<'a> {
-    description: &'a str,
-    pr_number: usize,
-    pr_suffix_start_index: usize,
-}
-
-impl<'a> GithubMergeCommit<'a> {
-    fn new(description: &'a str) -> Option<Self> {
-        /// State machine for the description, walking backwards.
-        enum State {
-            /// `)`
-            Close,
-            /// `123`
-            Number(usize),
-            /// `#`
-            Pound,
-            /// `(`
-            Open,
-            /// ` `
-            Space,
-            // Stop
-            Stop,
-        }
-
-        let mut index = description.len();
-        let mut number: Vec<char> = vec![];
-        let mut state = State::Close;
-        let mut chars = description.chars().rev();
-        let mut c = chars.next()?;
-        loop {
-            match state {
-                State::Close => {
-                    if c != ')' {
-                        return None;
-                    }
-
-                    c = chars.next()?;
-                    state = State::Number(0);
-                }
-                State::Number(count) => {
-                    if c.is_numeric() {
-                        number.insert(0, c);
-                        state = State::Number(count + 1);
-                        c = chars.next()?;
-                    } else if count == 0 {
-                        return None;
-                    } else {
-                        state = State::Pound;
-                    }
-                }
-                State::Pound => {
-                    if c != '#' {
-                        return None;
-                    }
-
-                    c = chars.next()?;
-                    state = State::Open;
-                }
-                State::Open => {
-                    if c != '(' {
-                        return None;
-                    }
-
-                    c = chars.next()?;
-                    state = State::Space;
-                }
-                State::Space => {
-                    if c != ' ' {
-                        return None;
-                    }
-
-                    state = State::Stop;
-                }
-                State::Stop => break,
-            };
-
-            index -= 1;
+        if let Some(merged) = merged {
+            state.serialize_field("merged_changes", merged)?;
         }
 
-        Some(Self {
-            description,
-            pr_number: number.into_iter().collect::<String>().parse().ok()?,
-            pr_suffix_start_index: index,
-        })
-    }
-
-    pub(crate) fn description(&self) -> &str {
-        &self.description[..=self.pr_suffix_start_index]
-    }
-
-    pub(crate) fn pr_number(&self) -> usize {
-        self.pr_number
+        state.end()
     }
 }
 
@@ -277,4 +367,45 @@ mod tests {
             assert_eq!(parse_contributor_footer(value), expected.map(Into::into));
         }
     }
+
+    #[test]
+    fn test_roles_mailmap() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        std::fs::write(
+            dir.path().join(".mailmap"),
+            "Jane Doe <jane@example.com> <jane@old.example.com>\n\
+             John Repo <john@repo.example.com> <john@example.com>\n",
+        )
+        .unwrap();
+
+        let config = Config {
+            mailmap: vec![
+                "Jane Old <jane@old.example.com> <jane@older.example.com>".to_owned(),
+                "John Doe <john@new.example.com> <john@example.com>".to_owned(),
+            ],
+            ..Config::default()
+        };
+        let mailmap = Mailmap::new(&repo, &config.mailmap).unwrap();
+
+        let mut commit = Commit::fixture(
+            "one",
+            "feat: one\n\nCo-authored-by: John <john@example.com>",
+        );
+        commit.author.email = "jane@older.example.com".to_owned();
+        let change = Change::new(&commit, &config, Some(&mailmap)).unwrap();
+
+        #[rustfmt::skip]
+        let expected = [
+            (("Jane Doe", "jane@example.com"), "author"),
+            (("Jane Doe", "jane@example.com"), "committer"),
+            (("John Doe", "john@new.example.com"), "co-authored-by"),
+        ];
+
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(contributor, role)| (Contributor::from(*contributor), role.to_string()))
+            .collect();
+        assert_eq!(change.roles(&config.contributor_footers), expected);
+    }
 }
@@ -1,22 +1,45 @@
-use crate::changelog::{Change, Contributor};
-use crate::git::{Commit, Tag};
-use crate::Error;
+use crate::changelog::{Bump, Change, Contribution, Contributor};
+use crate::forge::Forge;
+use crate::git::{self, Commit, IgnoreReason, Ignored, Mailmap, Tag};
+use crate::{Config, Error};
 use serde::ser::{SerializeStruct, Serializer};
 use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// A set of changes belonging together.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ChangeSet<'a> {
     /// Internal reference to the changes in this change set.
     changes: Vec<Change<'a>>,
-    contributor_footers: &'a [String],
+    config: &'a Config,
+
+    /// The mailmap used to resolve the canonical identity of contributors.
+    mailmap: Option<&'a Mailmap>,
+
+    /// The revision after which this change set starts, if any.
+    from: Option<String>,
+
+    /// The revision at which this change set ends.
+    to: String,
+
+    /// The contributors who did not contribute to any earlier change set.
+    new_contributors: Vec<Contributor>,
 }
 
 impl<'a> ChangeSet<'a> {
-    pub(crate) fn new(contributor_footers: &'a [String]) -> Self {
+    pub(crate) fn new(
+        config: &'a Config,
+        mailmap: Option<&'a Mailmap>,
+        from: Option<String>,
+        to: String,
+    ) -> Self {
         Self {
             changes: Vec::new(),
-            contributor_footers,
+            config,
+            mailmap,
+            from,
+            to,
+            new_contributors: Vec::new(),
         }
     }
 
@@ -36,7 +59,8 @@ impl<'a> ChangeSet<'a> {
     ///
     /// If any type filters are provided, any commit that would be part of the
     /// change set is removed from the commit list, but not added to the change
-    /// set.
+    /// set. These commits, and any non-conventional commits, are added to
+    /// `ignored` instead.
     ///
     /// # Important
     ///
@@ -57,8 +81,8 @@ impl<'a> ChangeSet<'a> {
         &mut self,
         mut offset: usize,
         commits: &'a [Commit],
-        accept_types: &Option<Vec<String>>,
         tag: Option<&Tag>,
+        ignored: &mut Vec<Ignored>,
     ) -> Result<usize, Error> {
         if commits.is_empty() {
             return Ok(offset);
@@ -66,13 +90,21 @@ impl<'a> ChangeSet<'a> {
 
         let idx = match tag {
             None => commits.iter().skip(offset).count(),
-            Some(tag) => {
-                commits
-                    .iter()
-                    .skip(offset)
-                    .take_while(|c| c.id != tag.commit.id)
-                    .count()
-                    + 1
+            // If the tag points to a commit that was already taken by an
+            // earlier change set, this change set is empty.
+            Some(tag) => commits
+                .iter()
+                .skip(offset)
+                .position(|c| c.id == tag.commit.id)
+                .map_or(0, |idx| idx + 1),
+        };
+
+        let (config, mailmap) = (self.config, self.mailmap);
+        let accepts = |c: &Change<'_>| {
+            if let Some(types) = &config.accept_types {
+                types.iter().any(|f| f == c.type_())
+            } else {
+                true
             }
         };
 
@@ -80,24 +112,83 @@ impl<'a> ChangeSet<'a> {
             .iter()
             .skip(offset)
             .take(idx)
-            .filter_map(|commit| Change::new(commit).ok())
+            .flat_map(|commit| {
+                // The commit releasing the change log is not a change of the
+                // release itself.
+                if tag.is_some_and(|tag| {
+                    tag.commit.id == commit.id
+                        && commit.message.trim_end() == git::release_commit_message(&tag.name)
+                }) {
+                    ignored.push(Ignored::commit(
+                        &commit.id,
+                        IgnoreReason::ReleaseCommit,
+                        None,
+                    ));
+                    return vec![];
+                }
+
+                let (merged, failed) = Change::from_merged(commit, config, mailmap);
+                for (commit, err) in failed {
+                    ignored.push(Ignored::commit(
+                        &commit.id,
+                        IgnoreReason::NonConventional,
+                        Some(err.to_string()),
+                    ));
+                }
+
+                match Change::new(commit, config, mailmap) {
+                    Ok(mut change) => {
+                        change.set_merged(merged);
+                        vec![change]
+                    }
+                    // A non-conventional merge commit is replaced by the
+                    // changes it merged, if any.
+                    Err(err) => {
+                        if merged.is_empty() {
+                            ignored.push(Ignored::commit(
+                                &commit.id,
+                                IgnoreReason::NonConventional,
+                                Some(err.to_string()),
+                            ));
+                        }
+                        merged
+                    }
+                }
+            })
             .collect::<Vec<_>>();
 
         offset += idx;
 
-        self.changes.append(
-            &mut changes
-                .into_iter()
-                .rev()
-                .filter(|c| {
-                    if let Some(types) = accept_types {
-                        types.iter().any(|f| f == c.type_())
-                    } else {
-                        true
-                    }
-                })
-                .collect(),
-        );
+        for mut change in changes.into_iter().rev() {
+            if !accepts(&change) {
+                ignored.push(Ignored::commit(
+                    change.id(),
+                    IgnoreReason::TypeNotAccepted,
+                    Some(format!("Type is not accepted: {}", change.type_())),
+                ));
+                for merged in change.merged() {
+                    ignored.push(Ignored::commit(
+                        merged.id(),
+                        IgnoreReason::TypeNotAccepted,
+                        Some(format!("Merge type is not accepted: {}", change.type_())),
+                    ));
+                }
+                continue;
+            }
+
+            change.retain_merged(|merged| {
+                let accepted = accepts(merged);
+                if !accepted {
+                    ignored.push(Ignored::commit(
+                        merged.id(),
+                        IgnoreReason::TypeNotAccepted,
+                        Some(format!("Type is not accepted: {}", merged.type_())),
+                    ));
+                }
+                accepted
+            });
+            self.changes.push(change);
+        }
 
         Ok(offset)
     }
@@ -107,6 +198,58 @@ impl<'a> ChangeSet<'a> {
         &self.changes
     }
 
+    /// Return the list of breaking changes in this change set, including
+    /// breaking changes merged by other changes.
+    pub(crate) fn breaking_changes(&self) -> Vec<&Change<'_>> {
+        self.all_changes().filter(|c| c.breaking()).collect()
+    }
+
+    /// Iterate over all changes, including the changes merged by other changes.
+    fn all_changes(&self) -> impl Iterator<Item = &Change<'a>> {
+        self.changes
+            .iter()
+            .flat_map(|c| std::iter::once(c).chain(c.merged()))
+    }
+
+    /// The version bump required by the changes in this change set.
+    ///
+    /// Breaking changes always require a major bump, any other change is
+    /// looked up by its type in the provided list of bumps. If none of the
+    /// changes require a bump, `None` is returned.
+    pub(crate) fn bump(&self, bumps: &HashMap<String, Bump>) -> Option<Bump> {
+        self.all_changes()
+            .filter_map(|c| {
+                if c.breaking() {
+                    Some(Bump::Major)
+                } else {
+                    bumps.get(c.type_()).copied()
+                }
+            })
+            .max()
+    }
+
+    /// The mailmap used to resolve the canonical identity of contributors.
+    pub(crate) fn mailmap(&self) -> Option<&'a Mailmap> {
+        self.mailmap
+    }
+
+    /// The forge hosting the repository, if any.
+    pub(crate) fn forge(&self) -> Option<&Forge> {
+        self.config.forge.as_ref()
+    }
+
+    /// The URL comparing the start and end of this change set, if any.
+    ///
+    /// If the change set has no start (e.g. there are no earlier releases),
+    /// this links to the history up to the end instead.
+    pub(crate) fn compare_url(&self) -> Option<String> {
+        let forge = self.forge()?;
+        match self.from.as_deref() {
+            Some(from) => forge.compare_url(from, &self.to),
+            None => forge.commits_url(&self.to),
+        }
+    }
+
     /// A list of people who contributed to this change set.
     ///
     /// You can pass in a list of optional contributor emails to ignore.
@@ -114,7 +257,7 @@ impl<'a> ChangeSet<'a> {
         let mut contributors: Vec<_> = self
             .changes
             .iter()
-            .flat_map(|v| Change::contributors(v, self.contributor_footers))
+            .flat_map(|v| Change::contributors(v, &self.config.contributor_footers))
             .filter(|c| !ignore.unwrap_or(&[]).iter().any(|email| email == &c.email))
             .collect();
 
@@ -122,6 +265,64 @@ impl<'a> ChangeSet<'a> {
         contributors.dedup();
         contributors
     }
+
+    /// Find the contributors to this change set not part of the given set of
+    /// known contributors, and add them to it.
+    ///
+    /// Change sets have to be provided from oldest to newest, for this to find
+    /// the first contribution of each contributor.
+    pub(crate) fn find_new_contributors(&mut self, known: &mut BTreeSet<Contributor>) {
+        self.new_contributors = self
+            .contributors(None)
+            .into_iter()
+            .filter(|c| known.insert(c.clone()))
+            .collect();
+    }
+
+    /// The contributors who did not contribute to any earlier change set.
+    pub(crate) fn new_contributors(&self) -> &[Contributor] {
+        &self.new_contributors
+    }
+
+    /// The contributions of the given contributors to this change set.
+    fn contributions<'b>(&self, contributors: &'b [Contributor]) -> Vec<Contribution<'b>> {
+        let footers = &self.config.contributor_footers;
+        let roles: Vec<_> = self
+            .all_changes()
+            .map(|change| (change.type_(), change.roles(footers)))
+            .collect();
+
+        contributors
+            .iter()
+            .map(|contributor| {
+                let mut contribution = Contribution {
+                    contributor,
+                    is_first_contribution: self.new_contributors.contains(contributor),
+                    changes: 0,
+                    types: BTreeMap::new(),
+                    roles: BTreeSet::new(),
+                };
+
+                for (type_, roles) in &roles {
+                    let mut roles = roles
+                        .iter()
+                        .filter(|(c, _)| c == contributor)
+                        .map(|(_, role)| role.clone())
+                        .peekable();
+
+                    if roles.peek().is_none() {
+                        continue;
+                    }
+
+                    contribution.changes += 1;
+                    *contribution.types.entry((*type_).to_owned()).or_default() += 1;
+                    contribution.roles.extend(roles);
+                }
+
+                contribution
+            })
+            .collect()
+    }
 }
 
 impl Serialize for ChangeSet<'_> {
@@ -129,9 +330,115 @@ impl Serialize for ChangeSet<'_> {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("ChangeSet", 2)?;
+        let urls = self
+            .compare_url()
+            .map(|url| ("compare", url))
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let contributors = self.contributors(None);
+
+        let mut state = serializer.serialize_struct("ChangeSet", 5)?;
         state.serialize_field("changes", &self.changes())?;
-        state.serialize_field("contributors", &self.contributors(None))?;
+        state.serialize_field("breaking_changes", &self.breaking_changes())?;
+        state.serialize_field("contributors", &self.contributions(&contributors))?;
+        state.serialize_field(
+            "new_contributors",
+            &self.contributions(self.new_contributors()),
+        )?;
+        state.serialize_field("urls", &urls)?;
         state.end()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_commits_release_commit() {
+        let config = Config::default();
+        let commits = [
+            Commit::fixture("one", "feat: one"),
+            Commit::fixture("two", "chore: Release v1.0.0"),
+            Commit::fixture("three", "chore: Release v1.0.0"),
+        ];
+
+        #[rustfmt::skip]
+        let cases = [
+            ("v1.0.0", 1, vec!["one"], vec!["two"]),
+            ("v1.0.0", 2, vec!["two", "one"], vec!["three"]),
+            ("v2.0.0", 1, vec!["two", "one"], vec![]),
+        ];
+
+        for (name, idx, expected_changes, expected_ignored) in cases {
+            let tag = Tag {
+                message: None,
+                name: name.to_owned(),
+                version: semver::Version::parse(&name[1..]).unwrap(),
+                tagger: None,
+                commit: commits[idx].clone(),
+            };
+
+            let mut changeset = ChangeSet::new(&config, None, None, name.to_owned());
+            let mut ignored = vec![];
+            changeset
+                .take_commits(0, &commits, Some(&tag), &mut ignored)
+                .unwrap();
+
+            let changes = changeset
+                .changes()
+                .iter()
+                .map(|c| c.id())
+                .collect::<Vec<_>>();
+            let ignored = ignored
+                .iter()
+                .map(|i| i.commit.as_deref().unwrap())
+                .collect::<Vec<_>>();
+
+            assert_eq!(changes, expected_changes, "{} {}", name, idx);
+            assert_eq!(ignored, expected_ignored, "{} {}", name, idx);
+        }
+    }
+
+    #[test]
+    fn test_take_commits_merge_expanded() {
+        let config = Config {
+            accept_types: Some(vec!["feat".to_owned()]),
+            ..Config::default()
+        };
+
+        #[rustfmt::skip]
+        let cases = [
+            ("feat: merge topic", vec!["merge", "one"], vec![("three", IgnoreReason::NonConventional), ("two", IgnoreReason::TypeNotAccepted)]),
+            ("Merge branch 'topic'", vec!["one"], vec![("three", IgnoreReason::NonConventional), ("two", IgnoreReason::TypeNotAccepted)]),
+            ("chore: merge topic", vec![], vec![("three", IgnoreReason::NonConventional), ("merge", IgnoreReason::TypeNotAccepted), ("two", IgnoreReason::TypeNotAccepted), ("one", IgnoreReason::TypeNotAccepted)]),
+        ];
+
+        for (message, expected_changes, expected_ignored) in cases {
+            let merged = vec![
+                Commit::fixture("one", "feat: one"),
+                Commit::fixture("two", "chore: two"),
+                Commit::fixture("three", "three"),
+            ];
+            let commits = [Commit {
+                merged,
+                ..Commit::fixture("merge", message)
+            }];
+            let mut changeset = ChangeSet::new(&config, None, None, "HEAD".to_owned());
+            let mut ignored = vec![];
+            changeset
+                .take_commits(0, &commits, None, &mut ignored)
+                .unwrap();
+
+            let changes = changeset.all_changes().map(|c| c.id()).collect::<Vec<_>>();
+            let ignored = ignored
+                .iter()
+                .map(|i| (i.commit.as_deref().unwrap(), i.reason))
+                .collect::<Vec<_>>();
+
+            assert_eq!(changes, expected_changes, "{}", message);
+            assert_eq!(ignored, expected_ignored, "{}", message);
+        }
+    }
+}
@@ -1,12 +1,28 @@
+use crate::git::Mailmap;
 use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
 
 /// The contributor to a change.
-#[derive(Debug, Serialize, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Serialize, Eq, PartialEq, PartialOrd, Ord)]
 pub(crate) struct Contributor {
     pub(crate) name: String,
     pub(crate) email: String,
 }
 
+impl Contributor {
+    /// The contributor with the given name and email, resolved to their
+    /// canonical identity using the mailmap, if any.
+    pub(crate) fn resolve(name: &str, email: &str, mailmap: Option<&Mailmap>) -> Self {
+        match mailmap {
+            Some(mailmap) => {
+                let (name, email) = mailmap.resolve(name.trim(), email.trim());
+                (name.as_str(), email.as_str()).into()
+            }
+            None => (name, email).into(),
+        }
+    }
+}
+
 impl From<(&str, &str)> for Contributor {
     fn from((name, email): (&str, &str)) -> Self {
         Self {
@@ -15,3 +31,23 @@ impl From<(&str, &str)> for Contributor {
         }
     }
 }
+
+/// The contributions of a contributor to a change set.
+#[derive(Debug, Serialize)]
+pub(crate) struct Contribution<'a> {
+    #[serde(flatten)]
+    pub(crate) contributor: &'a Contributor,
+
+    /// Whether the contributor did not contribute to any earlier change set.
+    pub(crate) is_first_contribution: bool,
+
+    /// The number of changes the contributor contributed to.
+    pub(crate) changes: usize,
+
+    /// The number of changes the contributor contributed to, per change type.
+    pub(crate) types: BTreeMap<String, usize>,
+
+    /// The roles in which the contributor contributed (e.g. `author` or
+    /// `reviewed-by`).
+    pub(crate) roles: BTreeSet<String>,
+}
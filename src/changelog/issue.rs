@@ -0,0 +1,99 @@
+use crate::config::IssueTracker;
+use crate::forge::Forge;
+use serde::Serialize;
+
+/// An issue referenced by a change.
+#[derive(Debug, Serialize, Eq, PartialEq)]
+pub(crate) struct Issue {
+    /// The name of the tracker the issue belongs to.
+    pub(crate) tracker: String,
+
+    /// The ID of the issue in the tracker (e.g. `12` or `PROJ-881`).
+    pub(crate) id: String,
+
+    /// The URL of the issue, if known.
+    pub(crate) url: Option<String>,
+}
+
+impl Issue {
+    /// Parse all issue references in a footer value (e.g. `#12, #34`), using
+    /// the first tracker matching each reference.
+    ///
+    /// References not matching any tracker are ignored.
+    pub(crate) fn parse_all(
+        value: &str,
+        trackers: &[IssueTracker],
+        forge: Option<&Forge>,
+    ) -> Vec<Self> {
+        value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|reference| !reference.is_empty())
+            .filter_map(|reference| {
+                trackers
+                    .iter()
+                    .find_map(|tracker| Self::parse(reference, tracker, forge))
+            })
+            .collect()
+    }
+
+    fn parse(reference: &str, tracker: &IssueTracker, forge: Option<&Forge>) -> Option<Self> {
+        let id = tracker.pattern.captures(reference)?.name("id")?.as_str();
+        let url = match &tracker.url {
+            Some(url) => Some(url.replace("{id}", id)),
+            None => forge.and_then(|forge| forge.issue_url(id)),
+        };
+
+        Some(Self {
+            tracker: tracker.name.clone(),
+            id: id.to_owned(),
+            url,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_parse_all() {
+        let trackers = [
+            IssueTracker::default(),
+            IssueTracker {
+                name: "jira".to_owned(),
+                pattern: Regex::new(r"^(?P<id>PROJ-\d+)$").unwrap(),
+                url: Some("https://example.atlassian.net/browse/{id}".to_owned()),
+            },
+        ];
+        let forge = Forge::github("foo/bar".to_owned());
+
+        #[rustfmt::skip]
+        let cases = [
+            ("#12", vec![("forge", "12", Some("https://github.com/foo/bar/issues/12"))]),
+            ("#12, #34", vec![("forge", "12", Some("https://github.com/foo/bar/issues/12")), ("forge", "34", Some("https://github.com/foo/bar/issues/34"))]),
+            ("PROJ-881", vec![("jira", "PROJ-881", Some("https://example.atlassian.net/browse/PROJ-881"))]),
+            ("#1 PROJ-2", vec![("forge", "1", Some("https://github.com/foo/bar/issues/1")), ("jira", "PROJ-2", Some("https://example.atlassian.net/browse/PROJ-2"))]),
+            ("OTHER-1", vec![]),
+            ("", vec![]),
+        ];
+
+        for (value, expected) in cases {
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|(tracker, id, url)| Issue {
+                    tracker: tracker.to_owned(),
+                    id: id.to_owned(),
+                    url: url.map(str::to_owned),
+                })
+                .collect();
+
+            assert_eq!(
+                Issue::parse_all(value, &trackers, Some(&forge)),
+                expected,
+                "{}",
+                value
+            );
+        }
+    }
+}
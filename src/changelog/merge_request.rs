@@ -0,0 +1,290 @@
+use serde::Serialize;
+
+/// The platform whose merge request conventions were detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Platform {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    Azure,
+}
+
+/// A merge (or pull) request referenced by a change.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct MergeRequest<'a> {
+    /// The description of the change, without the merge request reference.
+    pub(crate) description: &'a str,
+
+    /// The number of the merge request.
+    pub(crate) number: usize,
+
+    /// The platform whose conventions were detected.
+    pub(crate) platform: Platform,
+}
+
+/// Detects merge requests referenced by commits, following the conventions of
+/// a single platform.
+///
+/// Each detector is tried in the order of [`DETECTORS`], until one of them
+/// detects a merge request.
+pub(crate) trait Detector: Sync {
+    /// The platform whose conventions are detected.
+    fn platform(&self) -> Platform;
+
+    /// Detect a merge request referenced before the conventional commit
+    /// message (e.g. `Merged PR 123: feat: ...`), returning the number and the
+    /// remaining message.
+    ///
+    /// This runs before the message is parsed, since a prefixed message is not
+    /// a valid conventional commit.
+    fn detect_prefix<'a>(&self, _message: &'a str) -> Option<(usize, &'a str)> {
+        None
+    }
+
+    /// Detect a merge request in the description of a conventional commit, or
+    /// anywhere in its full message, returning the number and the description
+    /// without the reference.
+    fn detect<'a>(&self, _description: &'a str, _message: &str) -> Option<(usize, &'a str)> {
+        None
+    }
+}
+
+/// The available merge request detectors, in order of precedence.
+pub(crate) const DETECTORS: &[&dyn Detector] = &[&GitHub, &GitLab, &Bitbucket, &Azure];
+
+impl<'a> MergeRequest<'a> {
+    /// Strip a merge request reference preceding the given commit message, if
+    /// any, returning the remaining message, and the platform and number of the
+    /// merge request.
+    pub(crate) fn strip_prefix(message: &'a str) -> (&'a str, Option<(Platform, usize)>) {
+        DETECTORS
+            .iter()
+            .find_map(|d| {
+                d.detect_prefix(message)
+                    .map(|(number, message)| (message, Some((d.platform(), number))))
+            })
+            .unwrap_or((message, None))
+    }
+
+    /// Detect a merge request in the description or full message of a
+    /// conventional commit.
+    pub(crate) fn detect(description: &'a str, message: &str) -> Option<Self> {
+        DETECTORS.iter().find_map(|d| {
+            d.detect(description, message)
+                .map(|(number, description)| Self {
+                    description,
+                    number,
+                    platform: d.platform(),
+                })
+        })
+    }
+}
+
+/// GitHub squash merges, suffixing the description with `(#123)`.
+struct GitHub;
+
+impl Detector for GitHub {
+    fn platform(&self) -> Platform {
+        Platform::GitHub
+    }
+
+    fn detect<'a>(&self, description: &'a str, _message: &str) -> Option<(usize, &'a str)> {
+        GithubMergeCommit::new(description).map(|c| (c.pr_number(), c.description()))
+    }
+}
+
+/// GitLab merge commits, ending with a `See merge request group/project!123`
+/// trailer.
+struct GitLab;
+
+impl Detector for GitLab {
+    fn platform(&self) -> Platform {
+        Platform::GitLab
+    }
+
+    fn detect<'a>(&self, description: &'a str, message: &str) -> Option<(usize, &'a str)> {
+        message.lines().rev().find_map(|line| {
+            let (_, number) = line
+                .trim()
+                .strip_prefix("See merge request ")?
+                .rsplit_once('!')?;
+
+            Some((number.parse().ok()?, description))
+        })
+    }
+}
+
+/// Bitbucket merges, suffixing the description with `(pull request #123)`.
+struct Bitbucket;
+
+impl Detector for Bitbucket {
+    fn platform(&self) -> Platform {
+        Platform::Bitbucket
+    }
+
+    fn detect<'a>(&self, description: &'a str, _message: &str) -> Option<(usize, &'a str)> {
+        let (description, number) = description
+            .strip_suffix(')')?
+            .rsplit_once(" (pull request #")?;
+
+        Some((number.parse().ok()?, description))
+    }
+}
+
+/// Azure DevOps merges, prefixing the message with `Merged PR 123: `.
+struct Azure;
+
+impl Detector for Azure {
+    fn platform(&self) -> Platform {
+        Platform::Azure
+    }
+
+    fn detect_prefix<'a>(&self, message: &'a str) -> Option<(usize, &'a str)> {
+        let (number, message) = message.strip_prefix("Merged PR ")?.split_once(": ")?;
+
+        Some((number.parse().ok()?, message))
+    }
+}
+
+pub(crate) struct GithubMergeCommit<'a> {
+    description: &'a str,
+    pr_number: usize,
+    pr_suffix_start_index: usize,
+}
+
+impl<'a> GithubMergeCommit<'a> {
+    pub(crate) fn new(description: &'a str) -> Option<Self> {
+        /// State machine for the description, walking backwards.
+        enum State {
+            /// `)`
+            Close,
+            /// `123`
+            Number(usize),
+            /// `#`
+            Pound,
+            /// `(`
+            Open,
+            /// ` `
+            Space,
+            // Stop
+            Stop,
+        }
+
+        let mut index = description.len();
+        let mut number: Vec<char> = vec![];
+        let mut state = State::Close;
+        let mut chars = description.chars().rev();
+        let mut c = chars.next()?;
+        loop {
+            match state {
+                State::Close => {
+                    if c != ')' {
+                        return None;
+                    }
+
+                    c = chars.next()?;
+                    state = State::Number(0);
+                }
+                State::Number(count) => {
+                    if c.is_numeric() {
+                        number.insert(0, c);
+                        state = State::Number(count + 1);
+                        c = chars.next()?;
+                    } else if count == 0 {
+                        return None;
+                    } else {
+                        state = State::Pound;
+                    }
+                }
+                State::Pound => {
+                    if c != '#' {
+                        return None;
+                    }
+
+                    c = chars.next()?;
+                    state = State::Open;
+                }
+                State::Open => {
+                    if c != '(' {
+                        return None;
+                    }
+
+                    c = chars.next()?;
+                    state = State::Space;
+                }
+                State::Space => {
+                    if c != ' ' {
+                        return None;
+                    }
+
+                    state = State::Stop;
+                }
+                State::Stop => break,
+            };
+
+            index -= 1;
+        }
+
+        Some(Self {
+            description,
+            pr_number: number.into_iter().collect::<String>().parse().ok()?,
+            pr_suffix_start_index: index,
+        })
+    }
+
+    pub(crate) fn description(&self) -> &'a str {
+        &self.description[..=self.pr_suffix_start_index]
+    }
+
+    pub(crate) fn pr_number(&self) -> usize {
+        self.pr_number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        #[rustfmt::skip]
+        let cases = [
+            ("add x (#12)", "feat: add x (#12)", Some(("add x", 12, Platform::GitHub))),
+            ("add x", "feat: add x\n\nSee merge request group/project!34", Some(("add x", 34, Platform::GitLab))),
+            ("add x (pull request #56)", "feat: add x (pull request #56)", Some(("add x", 56, Platform::Bitbucket))),
+            ("add x (#)", "feat: add x (#)", None),
+            ("add x", "feat: add x\n\nSee merge request group/project", None),
+            ("add x", "feat: add x", None),
+        ];
+
+        for (description, message, expected) in cases {
+            let expected = expected.map(|(description, number, platform)| MergeRequest {
+                description,
+                number,
+                platform,
+            });
+
+            assert_eq!(
+                MergeRequest::detect(description, message),
+                expected,
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        #[rustfmt::skip]
+        let cases = [
+            ("Merged PR 78: feat: add x", ("feat: add x", Some((Platform::Azure, 78)))),
+            ("Merged PR x: feat: add x", ("Merged PR x: feat: add x", None)),
+            ("feat: add x", ("feat: add x", None)),
+        ];
+
+        for (message, expected) in cases {
+            assert_eq!(MergeRequest::strip_prefix(message), expected, "{}", message);
+        }
+    }
+}
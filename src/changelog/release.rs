@@ -1,10 +1,10 @@
-use crate::changelog::ChangeSet;
+use crate::changelog::{ChangeSet, Contributor};
 use crate::git::Tag;
-use crate::Error;
 use chrono::{offset::Utc, DateTime};
 use semver::Version;
 use serde::ser::{SerializeStruct, Serializer};
 use serde::Serialize;
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct Release<'a> {
@@ -23,7 +23,7 @@ impl Serialize for Release<'_> {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Release", 5)?;
+        let mut state = serializer.serialize_struct("Release", 8)?;
         state.serialize_field("version", &self.version())?;
         if let Some(subject) = self.subject() {
             state.serialize_field("subject", &subject)?;
@@ -32,31 +32,23 @@ impl Serialize for Release<'_> {
             state.serialize_field("notes", &notes)?;
         }
         state.serialize_field("date", &self.date())?;
+        state.serialize_field("tag", &self.tag.name)?;
+        if let Some(tagger) = self.tagger() {
+            state.serialize_field("tagger", &tagger)?;
+        }
         state.serialize_field("changeset", &self.changeset())?;
+        state.serialize_field("urls", &self.urls())?;
         state.end()
     }
 }
 
 impl<'a> Release<'a> {
-    pub(crate) fn new(tag: Tag) -> Result<Self, Error> {
-        let version = if tag.name.starts_with('v') {
-            &tag.name[1..]
-        } else {
-            &tag.name
-        };
-
-        let version = Version::parse(version)?;
-
-        Ok(Self {
-            version,
+    pub(crate) fn new(tag: Tag, changeset: ChangeSet<'a>) -> Self {
+        Self {
+            version: tag.version.clone(),
             tag,
-            changeset: ChangeSet::default(),
-        })
-    }
-
-    /// Add a set of changes to the release.
-    pub(crate) fn with_changeset(&mut self, changeset: ChangeSet<'a>) {
-        self.changeset = changeset;
+            changeset,
+        }
     }
 
     /// The SemVer version of the release.
@@ -108,8 +100,7 @@ impl<'a> Release<'a> {
             let begin = self.subject().and_then(|_| msg.find('\n')).unwrap_or(0);
             let end = msg
                 .find("-----BEGIN")
-                .unwrap_or(msg.len())
-                .saturating_sub(1);
+                .map_or(msg.len(), |idx| idx.saturating_sub(1));
 
             msg.get(begin..end).map(str::trim)
         })
@@ -136,13 +127,76 @@ impl<'a> Release<'a> {
             .unwrap_or_else(|| self.tag.commit.time)
     }
 
+    /// The person who tagged the release, resolved to their canonical identity.
+    ///
+    /// For lightweight tags, this is the author of the tagged commit.
+    pub(crate) fn tagger(&self) -> Option<Contributor> {
+        let tagger = self.tag.tagger.as_ref()?;
+        let mailmap = self.changeset.mailmap();
+
+        Some(Contributor::resolve(&tagger.name, &tagger.email, mailmap))
+    }
+
     /// The Git tag belonging to the release.
     pub fn tag(&self) -> &Tag {
         &self.tag
     }
 
+    /// The links to the release on the forge, if any.
+    pub(crate) fn urls(&self) -> HashMap<&'static str, String> {
+        let mut urls = HashMap::new();
+        if let Some(url) = self
+            .changeset
+            .forge()
+            .and_then(|f| f.tag_url(&self.tag.name))
+        {
+            urls.insert("tag", url);
+        }
+        if let Some(url) = self.changeset.compare_url() {
+            urls.insert("compare", url);
+        }
+
+        urls
+    }
+
     /// The change set belonging to the release.
     pub(crate) fn changeset(&self) -> &ChangeSet<'_> {
         &self.changeset
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::Commit;
+    use crate::Config;
+
+    fn release<'a>(config: &'a Config, message: &str) -> Release<'a> {
+        let commit = Commit::fixture(&"0".repeat(40), "feat: initial");
+        let tag = Tag {
+            message: Some(message.to_owned()),
+            name: "v1.0.0".to_owned(),
+            version: Version::new(1, 0, 0),
+            tagger: Some(commit.author.clone()),
+            commit,
+        };
+
+        Release::new(tag, ChangeSet::new(config, None, None, "v1.0.0".to_owned()))
+    }
+
+    #[test]
+    fn test_notes() {
+        #[rustfmt::skip]
+        let cases = [
+            ("Notes", Some("Notes")),
+            ("Title\n\nSome notes", Some("Some notes")),
+            ("Title\n\nSome notes\n-----BEGIN PGP SIGNATURE-----\n...", Some("Some notes")),
+            ("Title\n\n-----BEGIN PGP SIGNATURE-----\n...", Some("")),
+        ];
+
+        let config = Config::default();
+        for (message, expected) in cases {
+            assert_eq!(release(&config, message).notes(), expected, "{}", message);
+        }
+    }
+}
@@ -1,16 +1,36 @@
-use crate::{git, Error};
+use crate::changelog::Bump;
+use crate::forge::Forge;
+use crate::git::{self, RemoteUrl};
+use crate::Error;
 use git2::Repository;
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use ron::extensions::Extensions;
+use semver::Version;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use std::fs::read_to_string;
 use std::io;
-use url::Url;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
+    /// The GitHub repository, kept for backward compatibility. Prefer `forge`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub github: Option<Github>,
+
+    /// The forge hosting the repository, used to link to commits, pull
+    /// requests, issues, comparisons and tags.
+    ///
+    /// If not set, the forge is detected from the URL of the `remote`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forge: Option<Forge>,
+
+    /// The name of the Git remote used to detect the forge. Defaults to
+    /// `origin`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub accept_types: Option<Vec<String>>,
     #[serde(skip_serializing_if = "HashMap::is_empty")]
@@ -18,6 +38,23 @@ pub struct Config {
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     pub scope_headers: HashMap<String, String>,
 
+    /// The scopes allowed by `jilu check`. Any scope is allowed if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+
+    /// The version bump required for each commit type, used to calculate the
+    /// next version. Breaking changes always require a major bump.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub type_bumps: HashMap<String, Bump>,
+
+    /// The pattern used to find release tags, and extract their versions.
+    #[serde(skip_serializing_if = "TagPattern::is_default")]
+    pub tag_pattern: TagPattern,
+
+    /// The way the Git history is walked to find commits.
+    #[serde(skip_serializing_if = "HistoryMode::is_default")]
+    pub history_mode: HistoryMode,
+
     /// The root commit to start the change log from.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub root_commit: Option<String>,
@@ -26,12 +63,60 @@ pub struct Config {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub ignore_commits: Vec<String>,
 
+    /// The Git notes reference containing change log overrides of commits
+    /// (e.g. `refs/notes/changelog`), see [`Note`](crate::note::Note).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes_ref: Option<String>,
+
+    /// Limit the change log to commits touching the given paths.
+    #[serde(skip_serializing_if = "Paths::is_empty")]
+    pub paths: Paths,
+
     /// A list of footer tokens (e.g. `Co-authored-by`) to use to find contributors.
     pub contributor_footers: Vec<String>,
 
+    /// The footers and trackers used to find issues referenced by a change.
+    pub issues: Issues,
+
+    /// Additional `.mailmap` entries (e.g. `Jane Doe <jane@example.com>
+    /// <jane@old.example.com>`), resolved before the `.mailmap` of the
+    /// repository, see [`Mailmap`](crate::git::Mailmap).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mailmap: Vec<String>,
+
+    /// The path to the change log template file, taking precedence over the
+    /// `Template(...)` block of the change log.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_path: Option<PathBuf>,
+
+    /// A directory of template files (e.g. partials or macros), available to
+    /// the templates using `include`, `import` or `extends`, by their path
+    /// relative to the directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_dir: Option<PathBuf>,
+
+    /// The package details used by the `debian` and `rpm` formats.
+    pub package: Package,
+
+    /// Additional files rendered when writing the change log.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<Output>,
+
+    /// The feed details used by the `atom` and `rss` outputs.
+    pub feed: Feed,
+
+    /// The hand-written history of releases predating the generated change
+    /// log, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub legacy: Option<Legacy>,
+
     #[serde(skip)]
     pub template: Option<String>,
 
+    /// The template used to render the notes of a single release.
+    #[serde(skip)]
+    pub release_notes_template: Option<String>,
+
     #[serde(skip)]
     pub metadata: Option<String>,
 }
@@ -52,6 +137,12 @@ impl Default for Config {
             .map(|(k, v)| (k.to_owned(), v.to_owned()))
             .collect();
 
+        let type_bumps = HashMap::from([
+            ("feat".to_owned(), Bump::Minor),
+            ("fix".to_owned(), Bump::Patch),
+            ("perf".to_owned(), Bump::Patch),
+        ]);
+
         let contributor_footers = vec![
             "co-authored-by",
             "signed-off-by",
@@ -67,13 +158,30 @@ impl Default for Config {
 
         Self {
             github: None,
+            forge: None,
+            remote: None,
             accept_types: None,
             type_headers,
             scope_headers: HashMap::new(),
+            scopes: None,
+            type_bumps,
+            tag_pattern: TagPattern::default(),
+            history_mode: HistoryMode::default(),
             root_commit: None,
             ignore_commits: Vec::new(),
+            notes_ref: Some("refs/notes/changelog".to_owned()),
+            paths: Paths::default(),
             contributor_footers,
+            issues: Issues::default(),
+            mailmap: Vec::new(),
+            template_path: None,
+            template_dir: None,
+            package: Package::default(),
+            outputs: Vec::new(),
+            feed: Feed::default(),
+            legacy: None,
             template: None,
+            release_notes_template: None,
             metadata: None,
         }
     }
@@ -84,27 +192,469 @@ pub struct Github {
     pub repo: String,
 }
 
+/// The way the Git history is walked to find the commits of the change log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HistoryMode {
+    /// Only walk the first parent of each commit, ignoring any commits brought
+    /// in by merge commits.
+    #[default]
+    FirstParent,
+
+    /// Walk all commits reachable from `HEAD`.
+    Full,
+
+    /// Walk the first parent of each commit, and attach the commits brought in
+    /// by merge commits to the merge commit.
+    MergeExpanded,
+}
+
+impl HistoryMode {
+    fn is_default(&self) -> bool {
+        *self == Self::FirstParent
+    }
+}
+
+impl FromStr for HistoryMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-parent" => Ok(Self::FirstParent),
+            "full" => Ok(Self::Full),
+            "merge-expanded" => Ok(Self::MergeExpanded),
+            _ => Err(format!("unknown history mode: {}", s).into()),
+        }
+    }
+}
+
+/// The pattern used to determine which Git tags are releases, and how to
+/// extract the release version from the tag name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum TagPattern {
+    /// A SemVer version, with an optional `v` prefix (e.g. `v1.2.0`).
+    ///
+    /// New tags are created with the `v` prefix.
+    #[default]
+    SemVer,
+
+    /// A SemVer version, with a required prefix (e.g. `mycrate-v` for
+    /// `mycrate-v1.2.0`).
+    Prefix(String),
+
+    /// A regular expression with a `version` capture group (e.g.
+    /// `^api@(?P<version>.+)$` for `api@1.2.0`).
+    ///
+    /// New tags are created using `format`, in which `{version}` is replaced by
+    /// the release version (e.g. `api@{version}`).
+    Regex {
+        #[serde(serialize_with = "ser_regex", deserialize_with = "de_version_regex")]
+        pattern: Regex,
+        format: String,
+    },
+}
+
+impl TagPattern {
+    fn is_default(&self) -> bool {
+        matches!(self, Self::SemVer)
+    }
+
+    /// The version part of the given tag name, if the tag matches the pattern.
+    pub fn version<'a>(&self, name: &'a str) -> Option<&'a str> {
+        match self {
+            Self::SemVer => Some(name.strip_prefix('v').unwrap_or(name)),
+            Self::Prefix(prefix) => name.strip_prefix(prefix.as_str()),
+            Self::Regex { pattern, .. } => pattern
+                .captures(name)
+                .and_then(|c| c.name("version"))
+                .map(|m| m.as_str()),
+        }
+    }
+
+    /// The tag name for the given version.
+    pub fn name(&self, version: &Version) -> String {
+        match self {
+            Self::SemVer => format!("v{}", version),
+            Self::Prefix(prefix) => format!("{}{}", prefix, version),
+            Self::Regex { format, .. } => format.replace("{version}", &version.to_string()),
+        }
+    }
+}
+
+fn ser_regex<S: Serializer>(regex: &Regex, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(regex.as_str())
+}
+
+fn de_version_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    de_regex(deserializer, "version")
+}
+
+fn de_id_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    de_regex(deserializer, "id")
+}
+
+/// Deserialize a regular expression, requiring the given capture group.
+fn de_regex<'de, D: Deserializer<'de>>(deserializer: D, group: &str) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    let regex = Regex::new(&pattern).map_err(serde::de::Error::custom)?;
+    if !regex.capture_names().any(|name| name == Some(group)) {
+        return Err(serde::de::Error::custom(format!(
+            "pattern `{}` requires a `{}` capture group",
+            pattern, group
+        )));
+    }
+
+    Ok(regex)
+}
+
+/// The package details used to render the change log in the `debian` and
+/// `rpm` formats.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Package {
+    /// The name of the package. Defaults to the name of the repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The packaging revision, appended to the release version (e.g. `1` in
+    /// `1.2.0-1`).
+    pub revision: String,
+
+    /// The Debian distribution of the releases.
+    pub distribution: String,
+
+    /// The Debian urgency of the releases.
+    pub urgency: String,
+
+    /// The maintainer (e.g. `Jane Doe <jane@example.com>`), used for releases
+    /// without a tagger.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maintainer: Option<String>,
+}
+
+impl Default for Package {
+    fn default() -> Self {
+        Self {
+            name: None,
+            revision: "1".to_owned(),
+            distribution: "unstable".to_owned(),
+            urgency: "medium".to_owned(),
+            maintainer: None,
+        }
+    }
+}
+
+/// The feed details used to render the releases as an Atom or RSS feed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Feed {
+    /// The title of the feed. Defaults to the name of the package.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// The unique identifier of the feed. Defaults to the feed link, or a
+    /// `urn:jilu:` URN based on the package name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// The link to the project website. Defaults to the repository URL on the
+    /// forge, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+/// The hand-written history of releases predating the adoption of
+/// conventional commits, kept verbatim in the change log.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Legacy {
+    /// The latest version covered by the legacy history. Releases up to and
+    /// including this version are not generated from the Git history.
+    pub version: Version,
+
+    /// The file containing the legacy history. Defaults to the section of the
+    /// change log between the `<!-- jilu-legacy-start -->` and
+    /// `<!-- jilu-legacy-end -->` markers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+
+    #[serde(skip)]
+    pub history: Option<String>,
+}
+
+/// A file rendered when writing the change log, in addition to the change log
+/// itself.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Output {
+    /// The name of the template (e.g. `jilu/release-notes.md`, or a file in
+    /// the `template_dir`), or the path to a template file.
+    pub template: String,
+
+    /// The path of the file to write.
+    pub destination: PathBuf,
+
+    /// Only render a single release, identified by its version, `latest` for
+    /// the latest release, or `unreleased` for the unreleased changes.
+    ///
+    /// Single releases are rendered with the `config` and the selected
+    /// `release` as context, similar to the release notes template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+}
+
+/// The configuration used to find issues referenced in the footers of a
+/// change (e.g. `Closes #12` or `Refs: PROJ-881`).
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Issues {
+    /// The footer tokens (e.g. `Closes`) referencing issues, matched
+    /// case-insensitively.
+    pub footers: Vec<String>,
+
+    /// The issue trackers, tried in order until one matches the reference.
+    pub trackers: Vec<IssueTracker>,
+}
+
+impl Default for Issues {
+    fn default() -> Self {
+        let footers = vec![
+            "closes",
+            "close",
+            "closed",
+            "fixes",
+            "fix",
+            "fixed",
+            "resolves",
+            "resolve",
+            "resolved",
+            "refs",
+            "ref",
+            "references",
+            "related-to",
+            "see",
+        ]
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+        Self {
+            footers,
+            trackers: vec![IssueTracker::default()],
+        }
+    }
+}
+
+/// An issue tracker, matching issue references such as `#12` or `PROJ-881`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssueTracker {
+    /// The name of the tracker (e.g. `jira`).
+    pub name: String,
+
+    /// A regular expression matching a single issue reference, with an `id`
+    /// capture group (e.g. `^(?P<id>PROJ-\d+)$`).
+    #[serde(serialize_with = "ser_regex", deserialize_with = "de_id_regex")]
+    pub pattern: Regex,
+
+    /// The URL of an issue, in which `{id}` is replaced by the issue ID (e.g.
+    /// `https://example.atlassian.net/browse/{id}`).
+    ///
+    /// Defaults to the issue URL of the forge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl Default for IssueTracker {
+    /// The issue tracker of the forge, matching references such as `#12`.
+    fn default() -> Self {
+        Self {
+            name: "forge".to_owned(),
+            pattern: Regex::new(r"^#(?P<id>\d+)$").expect("valid regex"),
+            url: None,
+        }
+    }
+}
+
+/// A set of paths, relative to the repository root, used to limit the commits
+/// included in the change log.
+///
+/// Paths use the Git "pathspec" syntax, meaning a directory matches all files
+/// within it, and glob patterns (e.g. `crates/*/Cargo.toml`) are supported.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Paths {
+    /// Only include commits touching at least one of these paths.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Ignore changes to these paths, even if they match `include`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl Paths {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+}
+
+/// The names of the standalone configuration files, in order of precedence,
+/// looked up in the root of the repository.
+const CONFIG_FILES: &[&str] = &["jilu.ron", ".jilu.ron", "jilu.toml"];
+
+/// The markers surrounding the legacy history in the change log.
+const LEGACY_START: &str = "<!-- jilu-legacy-start -->";
+const LEGACY_END: &str = "<!-- jilu-legacy-end -->";
+
 impl Config {
-    pub fn from_environment(repo: &Repository, file: &str) -> Result<Self, Error> {
-        Ok(Self::from_file(file)?.unwrap_or_else(|| Self {
-            github: git::origin_url(repo)
-                .ok()
-                .and_then(|url| {
-                    Url::parse(&url).ok().map(|u| {
-                        u.path()
-                            .strip_suffix(".git")
-                            .unwrap_or(u.path())
-                            .strip_prefix("/")
-                            .unwrap_or(u.path())
-                            .to_owned()
-                    })
-                })
-                .map(|repo| Github { repo }),
-            ..Default::default()
-        }))
-    }
-
-    fn from_file(name: &str) -> Result<Option<Self>, Error> {
+    /// Load the configuration for the given repository and change log file.
+    ///
+    /// The configuration is read from the first of these sources that exists:
+    ///
+    /// 1. The provided configuration file (e.g. using `--config`).
+    /// 2. A `jilu.ron`, `.jilu.ron` or `jilu.toml` file in the root of the
+    ///    repository.
+    /// 3. The `Config(...)` block at the end of the change log file.
+    /// 4. The default configuration.
+    ///
+    /// The `Config(...)` block is only parsed if no configuration file exists,
+    /// otherwise it is ignored with a warning.
+    ///
+    /// The `Template(...)` and `ReleaseNotes(...)` blocks of the change log file
+    /// are used regardless of the source of the configuration.
+    pub fn from_environment(
+        repo: &Repository,
+        file: &str,
+        config: Option<&Path>,
+    ) -> Result<Self, Error> {
+        let inline = Self::read_blocks(file)?;
+        let path = match config {
+            Some(path) => Some(path.to_owned()),
+            None => repo.workdir().and_then(|root| {
+                CONFIG_FILES
+                    .iter()
+                    .map(|name| root.join(name))
+                    .find(|path| path.is_file())
+            }),
+        };
+
+        let inline_config = inline.as_ref().and_then(|blocks| blocks.config.as_deref());
+        let mut config = match (path, inline_config) {
+            (Some(path), inline_config) => {
+                if inline_config.is_some() {
+                    eprintln!(
+                        "Warning: ignoring the Config(...) block of {} in favor of {}",
+                        file,
+                        path.display()
+                    );
+                }
+                Self::from_path(&path)?
+            }
+            (None, Some(inline_config)) => {
+                ron::de::from_str(inline_config).map_err(ron::Error::from)?
+            }
+            (None, None) => Self::default(),
+        };
+
+        if let Some(inline) = inline {
+            config.template = inline.template;
+            config.release_notes_template = inline.release_notes_template;
+            config.metadata = inline.metadata;
+        }
+
+        if let Some(legacy) = &mut config.legacy {
+            legacy.history = match &legacy.file {
+                Some(path) => Some(read_to_string(path).map_err(|err| {
+                    format!("Cannot read legacy history {}: {}", path.display(), err)
+                })?),
+                None => Self::legacy_history(file)?,
+            }
+            .map(|history| history.trim().to_owned());
+        }
+
+        if config.forge.is_none() {
+            config.forge = match &config.github {
+                Some(github) => Some(Forge::github(github.repo.clone())),
+                None => git::remote_url(repo, config.remote.as_deref().unwrap_or("origin"))
+                    .ok()
+                    .and_then(|url| RemoteUrl::parse(&url))
+                    .map(Forge::from_remote),
+            };
+
+            // Keep exposing the detected repository as `github` for backward
+            // compatibility with existing templates.
+            if config.github.is_none() {
+                config.github = config.forge.as_ref().map(|forge| Github {
+                    repo: forge.repo.clone(),
+                });
+            }
+        }
+
+        if config.package.name.is_none() {
+            config.package.name = match &config.forge {
+                Some(forge) => forge.repo.rsplit('/').next().map(str::to_owned),
+                None => repo
+                    .workdir()
+                    .and_then(Path::file_name)
+                    .map(|name| name.to_string_lossy().into_owned()),
+            };
+        }
+
+        let name = config.package.name.clone().unwrap_or_default();
+        let feed = &mut config.feed;
+        if feed.title.is_none() {
+            feed.title = Some(name.clone());
+        }
+        if feed.link.is_none() {
+            feed.link = config.forge.as_ref().map(Forge::repo_url);
+        }
+        if feed.id.is_none() {
+            feed.id = Some(match &feed.link {
+                Some(link) => link.clone(),
+                None => format!("urn:jilu:{}", name),
+            });
+        }
+
+        Ok(config)
+    }
+
+    /// Read a standalone configuration file, either in RON or TOML format,
+    /// based on the file extension.
+    fn from_path(path: &Path) -> Result<Self, Error> {
+        let text = read_to_string(path)
+            .map_err(|err| format!("Cannot read configuration file {}: {}", path.display(), err))?;
+
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            return Ok(toml::from_str(&text)?);
+        }
+
+        Ok(ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(&text)
+            .map_err(ron::Error::from)?)
+    }
+
+    /// Read the legacy history between the markers of the change log file, if
+    /// any.
+    fn legacy_history(name: &str) -> Result<Option<String>, Error> {
+        let text = match read_to_string(name) {
+            Ok(file) => file,
+            Err(err) => match err.kind() {
+                io::ErrorKind::NotFound => return Ok(None),
+                _ => return Err(err.into()),
+            },
+        };
+
+        let history = text
+            .split_once(LEGACY_START)
+            .and_then(|(_, rest)| rest.split_once(LEGACY_END))
+            .map(|(history, _)| history.to_owned());
+
+        Ok(history)
+    }
+
+    /// Read the blocks of the metadata comment at the end of the change log
+    /// file, if it contains a `Config(...)`, `Template(...)` or
+    /// `ReleaseNotes(...)` block.
+    fn read_blocks(name: &str) -> Result<Option<Blocks>, Error> {
         let text = match read_to_string(name) {
             Ok(file) => file,
             Err(err) => match err.kind() {
@@ -133,6 +683,7 @@ impl Config {
 
         let mut config: Vec<&str> = vec![];
         let mut template: Vec<&str> = vec![];
+        let mut release_notes: Vec<&str> = vec![];
         let mut bit = 0;
         for line in &metadata {
             // Start configuration fetching.
@@ -171,18 +722,155 @@ impl Config {
             // Continue template fetching.
             if bit == 2 {
                 template.push(line);
+                continue;
+            }
+
+            // Start release notes template fetching.
+            if line.trim_end() == "ReleaseNotes(" {
+                bit = 3;
+                continue;
             }
+
+            // End release notes template fetching.
+            if bit == 3 && line.trim_end() == ")" {
+                bit = 0;
+                continue;
+            }
+
+            // Continue release notes template fetching.
+            if bit == 3 {
+                release_notes.push(line);
+            }
+        }
+
+        let blocks = Blocks {
+            config: (!config.is_empty()).then(|| config.join("\n")),
+            template: (!template.is_empty()).then(|| template.join("\n")),
+            release_notes_template: (!release_notes.is_empty()).then(|| release_notes.join("\n")),
+            metadata: (!metadata.is_empty()).then(|| metadata.join("\n")),
+        };
+
+        // Any other comment at the end of the change log (e.g. one rendered by
+        // the template) is not metadata.
+        let found = blocks.config.is_some()
+            || blocks.template.is_some()
+            || blocks.release_notes_template.is_some();
+
+        Ok(found.then_some(blocks))
+    }
+}
+
+/// The blocks of the metadata comment at the end of a change log file.
+struct Blocks {
+    /// The unparsed `Config(...)` block, if any.
+    config: Option<String>,
+
+    /// The contents of the `Template(...)` block, if any.
+    template: Option<String>,
+
+    /// The contents of the `ReleaseNotes(...)` block, if any.
+    release_notes_template: Option<String>,
+
+    /// The full metadata comment.
+    metadata: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_history() {
+        #[rustfmt::skip]
+        let cases = [
+            ("# Changelog\n<!-- jilu-legacy-start -->\n## 0.1.0\n<!-- jilu-legacy-end -->\n", Some("\n## 0.1.0\n")),
+            ("<!-- jilu-legacy-start -->## 0.1.0<!-- jilu-legacy-end --><!-- jilu-legacy-end -->", Some("## 0.1.0")),
+            ("# Changelog\n<!-- jilu-legacy-start -->\n## 0.1.0\n", None),
+            ("# Changelog\n## 0.1.0\n<!-- jilu-legacy-end -->\n", None),
+            ("<!-- jilu-legacy-end -->\n## 0.1.0\n<!-- jilu-legacy-start -->", None),
+            ("# Changelog\n", None),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("CHANGELOG.md");
+        let name = file.to_str().unwrap();
+        assert_eq!(Config::legacy_history(name).unwrap(), None);
+
+        for (contents, expected) in cases {
+            std::fs::write(&file, contents).unwrap();
+            let history = Config::legacy_history(name).unwrap();
+            assert_eq!(history.as_deref(), expected, "{}", contents);
         }
+    }
 
-        let config = config.join("\n");
+    #[test]
+    fn test_tag_pattern() {
+        #[rustfmt::skip]
+        let cases = [
+            ("SemVer", "v1.2.0", Some("1.2.0"), "v1.2.0"),
+            ("SemVer", "1.2.0", Some("1.2.0"), "v1.2.0"),
+            ("Prefix(\"mycrate-v\")", "mycrate-v1.2.0", Some("1.2.0"), "mycrate-v1.2.0"),
+            ("Prefix(\"mycrate-v\")", "v1.2.0", None, "mycrate-v1.2.0"),
+            ("Regex(pattern: \"^api@(?P<version>.+)$\", format: \"api@{version}\")", "api@1.2.0", Some("1.2.0"), "api@1.2.0"),
+            ("Regex(pattern: \"^api@(?P<version>.+)$\", format: \"api@{version}\")", "cli@1.2.0", None, "api@1.2.0"),
+        ];
 
-        Ok(if config.contains("Config") {
-            let mut config: Config = ron::de::from_str(&config).map_err(ron::Error::from)?;
-            config.template = (!template.is_empty()).then_some(template.join("\n"));
-            config.metadata = (!metadata.is_empty()).then_some(metadata.join("\n"));
-            Some(config)
-        } else {
-            None
-        })
+        let version = Version::new(1, 2, 0);
+        for (pattern, tag, expected, name) in cases {
+            let pattern: TagPattern = ron::de::from_str(pattern).unwrap();
+            assert_eq!(pattern.version(tag), expected, "{:?} {}", pattern, tag);
+            assert_eq!(pattern.name(&version), name, "{:?}", pattern);
+        }
+
+        #[rustfmt::skip]
+        let invalid = [
+            ("Regex(pattern: \"^api@(.+)$\", format: \"api@{version}\")", "requires a `version` capture group"),
+            ("Regex(pattern: \"^api@(?P<version>.+$\", format: \"api@{version}\")", "unclosed group"),
+        ];
+
+        for (pattern, expected) in invalid {
+            let err = ron::de::from_str::<TagPattern>(pattern).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}: {}", pattern, err);
+        }
+    }
+
+    #[test]
+    fn test_from_environment_precedence() {
+        let inline = "# Changelog\n\n<!--\nConfig(\n  accept_types: [\"inline\"],\n)\n-->\n";
+        let template = "# Changelog\n\n<!--\nTemplate(\n{{ custom }}\n)\n-->\n";
+
+        #[rustfmt::skip]
+        let cases = [
+            (vec![], None, None, None),
+            (vec![("CHANGELOG.md", inline)], None, Some("inline"), None),
+            (vec![("CHANGELOG.md", inline), ("jilu.toml", "accept_types = [\"toml\"]")], None, Some("toml"), None),
+            (vec![("CHANGELOG.md", inline), ("jilu.toml", "accept_types = [\"toml\"]"), ("jilu.ron", "(accept_types: [\"ron\"])")], None, Some("ron"), None),
+            (vec![("CHANGELOG.md", inline), ("jilu.ron", "(accept_types: [\"ron\"])"), ("custom.ron", "(accept_types: [\"custom\"])")], Some("custom.ron"), Some("custom"), None),
+            (vec![("CHANGELOG.md", "<!--\nConfig(\n  invalid\n)\n-->\n"), ("jilu.ron", "(accept_types: [\"ron\"])")], None, Some("ron"), None),
+            (vec![("CHANGELOG.md", template), ("jilu.ron", "(accept_types: [\"ron\"])")], None, Some("ron"), Some("{{ custom }}")),
+            (vec![("CHANGELOG.md", "# Changelog\n\n<!-- [releases] -->\n")], None, None, None),
+        ];
+
+        for (files, explicit, expected, expected_template) in cases {
+            let dir = tempfile::tempdir().unwrap();
+            let repo = Repository::init(dir.path()).unwrap();
+            for (name, contents) in &files {
+                std::fs::write(dir.path().join(name), contents).unwrap();
+            }
+
+            let changelog = dir.path().join("CHANGELOG.md");
+            let explicit = explicit.map(|name| dir.path().join(name));
+            let config =
+                Config::from_environment(&repo, changelog.to_str().unwrap(), explicit.as_deref())
+                    .unwrap();
+
+            let types = config.accept_types.unwrap_or_default();
+            assert_eq!(types.first().map(String::as_str), expected, "{:?}", files);
+            assert_eq!(config.template.as_deref(), expected_template, "{:?}", files);
+            if expected_template.is_some() {
+                let metadata = config.metadata.unwrap_or_default();
+                assert!(metadata.contains("Template("), "{:?}", files);
+            }
+        }
     }
 }
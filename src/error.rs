@@ -6,6 +6,9 @@ pub enum Error {
     /// Configuration error
     Config(ron::de::Error),
 
+    /// TOML configuration error
+    TomlConfig(toml::de::Error),
+
     /// CLI error
     Cli(lexopt::Error),
 
@@ -15,6 +18,9 @@ pub enum Error {
     /// A formatting error.
     Format(fmt::Error),
 
+    /// The working tree has uncommitted changes.
+    DirtyWorkingTree,
+
     /// A generic error.
     Generic(String),
 
@@ -24,6 +30,9 @@ pub enum Error {
     /// The commit type is not accepted.
     InvalidCommitType,
 
+    /// The given number of commits do not follow the conventions.
+    InvalidCommits(usize),
+
     /// The provided Git tag is invalid.
     InvalidTag,
 
@@ -36,6 +45,9 @@ pub enum Error {
     /// A SemVer related error.
     SemVer(semver::Error),
 
+    /// A Git tag with the given name already exists.
+    TagExists(String),
+
     /// A templating error.
     Template(tera::Error),
 
@@ -59,16 +71,20 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Config(ref err) => write!(f, "Configuration error: {}", err),
+            TomlConfig(ref err) => write!(f, "Configuration error: {}", err),
             Cli(ref err) => write!(f, "CLI error: {}", err),
             ConventionalCommit(ref err) => write!(f, "Conventional Commit error: {}", err),
+            DirtyWorkingTree => f.write_str("Working tree has uncommitted changes"),
             Format(ref err) => write!(f, "Format error: {}", err),
             Generic(ref string) => write!(f, "Unknown error: {}", string),
             InvalidCommitType => f.write_str("Invalid commit type"),
+            InvalidCommits(count) => write!(f, "Found {} invalid commit(s)", count),
             InvalidTag => f.write_str("Invalid Git tag"),
             IO(ref err) => write!(f, "IO error: {}", err),
             Git(ref err) => write!(f, "Git error: {}", err),
             MissingCommitMessage => f.write_str("Missing commit message"),
             SemVer(ref err) => write!(f, "SemVer error: {}", err),
+            TagExists(ref name) => write!(f, "Git tag already exists: {}", name),
             Template(ref err) => write!(f, "Template error: {}", {
                 use std::error::Error as _;
 
@@ -93,6 +109,7 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Config(ref err) => Some(err),
+            TomlConfig(ref err) => Some(err),
             Cli(ref err) => Some(err),
             ConventionalCommit(ref err) => Some(err),
             Format(ref err) => Some(err),
@@ -103,8 +120,8 @@ impl error::Error for Error {
             Timestamp(ref err) => Some(err),
             Json(ref err) => Some(err),
 
-            Generic(_) | InvalidCommitType | InvalidTag | MissingCommitMessage | Utf8Error
-            | Jq(_) => None,
+            DirtyWorkingTree | Generic(_) | InvalidCommitType | InvalidCommits(_) | InvalidTag
+            | MissingCommitMessage | TagExists(_) | Utf8Error | Jq(_) => None,
         }
     }
 }
@@ -163,6 +180,12 @@ impl From<ron::de::Error> for Error {
     }
 }
 
+impl From<toml::de::Error> for Error {
+    fn from(err: toml::de::Error) -> Self {
+        Error::TomlConfig(err)
+    }
+}
+
 impl From<tera::Error> for Error {
     fn from(err: tera::Error) -> Self {
         Error::Template(err)
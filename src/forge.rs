@@ -0,0 +1,258 @@
+use crate::git::RemoteUrl;
+use serde::{Deserialize, Serialize};
+
+/// The hosting platform ("forge") of the repository, used to link to commits,
+/// pull requests, issues, comparisons and tags.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Forge {
+    /// The kind of forge, which determines the default URL templates.
+    pub kind: ForgeKind,
+
+    /// The base URL of the forge (e.g. `https://gitlab.example.com`).
+    ///
+    /// Defaults to the public instance of the forge kind, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// The path of the repository on the forge (e.g. `owner/repo`).
+    pub repo: String,
+
+    /// URL templates overriding the defaults of the forge kind.
+    #[serde(skip_serializing_if = "ForgeUrls::is_empty")]
+    pub urls: ForgeUrls,
+}
+
+/// The kind of forge hosting the repository.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForgeKind {
+    #[default]
+    GitHub,
+    GitLab,
+    Gitea,
+    Bitbucket,
+    Custom,
+}
+
+/// URL templates used to link to the forge.
+///
+/// The following placeholders are replaced in each template:
+///
+/// - `{url}`: the URL of the repository (e.g. `https://github.com/owner/repo`)
+/// - `{id}` and `{short_id}`: the (short) commit ID, for `commit`
+/// - `{number}`: the pull request or issue number, for `pull_request` and
+///   `issue`
+/// - `{from}` and `{to}`: the compared revisions, for `compare`
+/// - `{to}`: the last revision, for `commits`
+/// - `{tag}`: the tag name, for `tag`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForgeUrls {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compare: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commits: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+impl ForgeUrls {
+    fn is_empty(&self) -> bool {
+        self.commit.is_none()
+            && self.pull_request.is_none()
+            && self.issue.is_none()
+            && self.compare.is_none()
+            && self.commits.is_none()
+            && self.tag.is_none()
+    }
+}
+
+impl ForgeKind {
+    /// The base URL of the public instance of the forge, if any.
+    fn base_url(self) -> Option<&'static str> {
+        match self {
+            Self::GitHub => Some("https://github.com"),
+            Self::GitLab => Some("https://gitlab.com"),
+            Self::Gitea => Some("https://gitea.com"),
+            Self::Bitbucket => Some("https://bitbucket.org"),
+            Self::Custom => None,
+        }
+    }
+
+    /// The default URL template of the forge for the given link.
+    fn template(self, link: Link) -> Option<&'static str> {
+        use Link::*;
+
+        Some(match (self, link) {
+            (Self::GitHub | Self::Gitea, Commit) => "{url}/commit/{id}",
+            (Self::GitHub, PullRequest) => "{url}/pull/{number}",
+            (Self::GitHub | Self::Gitea | Self::Bitbucket, Issue) => "{url}/issues/{number}",
+            (Self::GitHub | Self::Gitea, Compare) => "{url}/compare/{from}...{to}",
+            (Self::GitHub | Self::Gitea | Self::Bitbucket, Commits) => "{url}/commits",
+            (Self::GitHub | Self::Gitea, Tag) => "{url}/releases/tag/{tag}",
+            (Self::GitLab, Commit) => "{url}/-/commit/{id}",
+            (Self::GitLab, PullRequest) => "{url}/-/merge_requests/{number}",
+            (Self::GitLab, Issue) => "{url}/-/issues/{number}",
+            (Self::GitLab, Compare) => "{url}/-/compare/{from}...{to}",
+            (Self::GitLab, Commits) => "{url}/-/commits",
+            (Self::GitLab, Tag) => "{url}/-/tags/{tag}",
+            (Self::Gitea, PullRequest) => "{url}/pulls/{number}",
+            (Self::Bitbucket, Commit) => "{url}/commits/{id}",
+            (Self::Bitbucket, PullRequest) => "{url}/pull-requests/{number}",
+            (Self::Bitbucket, Compare) => "{url}/branches/compare/{to}%0D{from}",
+            (Self::Bitbucket, Tag) => "{url}/src/{tag}",
+            (Self::Custom, _) => return None,
+        })
+    }
+}
+
+/// The kinds of links to the forge.
+#[derive(Clone, Copy)]
+enum Link {
+    Commit,
+    PullRequest,
+    Issue,
+    Compare,
+    Commits,
+    Tag,
+}
+
+impl Forge {
+    /// Create a GitHub forge for the given repository.
+    pub fn github(repo: String) -> Self {
+        Self {
+            kind: ForgeKind::GitHub,
+            repo,
+            ..Default::default()
+        }
+    }
+
+    /// Create a forge for the given remote.
+    ///
+    /// The kind of forge is determined by the host of the remote, defaulting
+    /// to GitHub (e.g. GitHub Enterprise) for unknown hosts.
+    pub fn from_remote(remote: RemoteUrl) -> Self {
+        let host = remote.host.to_ascii_lowercase();
+        let kind = if host.contains("gitlab") {
+            ForgeKind::GitLab
+        } else if host.contains("gitea") || host.contains("codeberg") {
+            ForgeKind::Gitea
+        } else if host.contains("bitbucket") {
+            ForgeKind::Bitbucket
+        } else {
+            ForgeKind::GitHub
+        };
+
+        Self {
+            kind,
+            url: Some(remote.base_url),
+            repo: remote.path,
+            ..Default::default()
+        }
+    }
+
+    /// The URL of the repository.
+    pub fn repo_url(&self) -> String {
+        let base = self.url.as_deref().or_else(|| self.kind.base_url());
+        match base {
+            Some(base) => format!("{}/{}", base.trim_end_matches('/'), self.repo),
+            None => self.repo.clone(),
+        }
+    }
+
+    /// The URL of a commit.
+    pub fn commit_url(&self, id: &str, short_id: &str) -> Option<String> {
+        self.url(
+            Link::Commit,
+            self.urls.commit.as_deref(),
+            &[("id", id), ("short_id", short_id)],
+        )
+    }
+
+    /// The URL of a pull request (or merge request).
+    pub fn pull_request_url(&self, number: &str) -> Option<String> {
+        self.url(
+            Link::PullRequest,
+            self.urls.pull_request.as_deref(),
+            &[("number", number)],
+        )
+    }
+
+    /// The URL of an issue.
+    pub fn issue_url(&self, number: &str) -> Option<String> {
+        self.url(
+            Link::Issue,
+            self.urls.issue.as_deref(),
+            &[("number", number)],
+        )
+    }
+
+    /// The URL comparing two revisions.
+    pub fn compare_url(&self, from: &str, to: &str) -> Option<String> {
+        self.url(
+            Link::Compare,
+            self.urls.compare.as_deref(),
+            &[("from", from), ("to", to)],
+        )
+    }
+
+    /// The URL of the history up to a revision, used when there is no earlier
+    /// revision to compare with.
+    pub fn commits_url(&self, to: &str) -> Option<String> {
+        self.url(Link::Commits, self.urls.commits.as_deref(), &[("to", to)])
+    }
+
+    /// The URL of a tag.
+    pub fn tag_url(&self, tag: &str) -> Option<String> {
+        self.url(Link::Tag, self.urls.tag.as_deref(), &[("tag", tag)])
+    }
+
+    fn url(&self, link: Link, template: Option<&str>, vars: &[(&str, &str)]) -> Option<String> {
+        let template = template.or_else(|| self.kind.template(link))?;
+        let mut url = template.replace("{url}", &self.repo_url());
+        for (key, value) in vars {
+            url = url.replace(&format!("{{{}}}", key), value);
+        }
+
+        Some(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        let mut forge = Forge::github("owner/repo".to_owned());
+        assert_eq!(
+            forge.compare_url("v1.0.0", "HEAD").as_deref(),
+            Some("https://github.com/owner/repo/compare/v1.0.0...HEAD")
+        );
+        assert_eq!(
+            forge.commits_url("HEAD").as_deref(),
+            Some("https://github.com/owner/repo/commits")
+        );
+
+        forge.kind = ForgeKind::GitLab;
+        forge.url = Some("https://git.example.com/".to_owned());
+        assert_eq!(
+            forge.pull_request_url("12").as_deref(),
+            Some("https://git.example.com/owner/repo/-/merge_requests/12")
+        );
+
+        forge.kind = ForgeKind::Custom;
+        forge.urls.commit = Some("{url}/c/{short_id}".to_owned());
+        assert_eq!(
+            forge.commit_url("abcdef", "abc").as_deref(),
+            Some("https://git.example.com/owner/repo/c/abc")
+        );
+        assert_eq!(forge.tag_url("v1.0.0"), None);
+    }
+}
@@ -1,7 +1,10 @@
 pub mod changelog;
 pub mod config;
 pub mod error;
+pub mod forge;
 pub mod git;
+pub mod lint;
+pub mod note;
 mod render;
 
 pub use changelog::Changelog;
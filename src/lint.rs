@@ -0,0 +1,241 @@
+use crate::changelog::MergeRequest;
+use crate::Config;
+use conventional::{Commit as CCommit, ErrorKind, Simple as _};
+use std::fmt;
+use std::ops::Range;
+
+/// A problem preventing a commit message from being (correctly) included in
+/// the change log.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The message is not a conventional commit.
+    NonConventional(conventional::Error),
+
+    /// The type is not part of `accept_types`, or `type_headers` if no types
+    /// are explicitly accepted.
+    UnknownType(String),
+
+    /// The scope is not part of the configured `scopes`.
+    UnknownScope(String),
+
+    /// A contributor footer (e.g. `Co-authored-by`) without a `Name <email>`
+    /// value.
+    InvalidContributorFooter(String),
+}
+
+impl Problem {
+    /// The line and the byte range in that line of the offending part of the
+    /// message, if known.
+    fn locate(&self, message: &str) -> Option<(usize, Range<usize>)> {
+        // The header is checked without a merge request prefix, if any.
+        let (stripped, _) = MergeRequest::strip_prefix(message);
+        let offset = message.len() - stripped.len();
+        let header = stripped.lines().next().unwrap_or_default();
+        let colon = header.find(':').unwrap_or(header.len());
+
+        let range = match self {
+            Problem::UnknownType(type_) => 0..type_.len(),
+            Problem::UnknownScope(scope) => {
+                let start = header.find('(')? + 1;
+                start..start + scope.len()
+            }
+            Problem::InvalidContributorFooter(token) => {
+                let (line, footer) = message
+                    .lines()
+                    .enumerate()
+                    .skip(1)
+                    .find(|(_, line)| line.starts_with(token.as_str()))?;
+
+                return Some((line, 0..footer.len()));
+            }
+            Problem::NonConventional(err) => match err.kind() {
+                ErrorKind::MissingType => {
+                    0..header.find(|c: char| !c.is_alphanumeric()).unwrap_or(0)
+                }
+                ErrorKind::InvalidScope => {
+                    let start = header.find('(').unwrap_or(colon);
+                    start
+                        ..header[start..]
+                            .find(')')
+                            .map_or(colon, |end| start + end + 1)
+                }
+                ErrorKind::MissingDescription => colon + 1..header.len(),
+                ErrorKind::InvalidBody => return message.lines().nth(1).map(|l| (1, 0..l.len())),
+                _ => colon..colon,
+            },
+        };
+
+        Some((0, offset + range.start..offset + range.end))
+    }
+
+    /// A human readable diagnostic of the problem, pointing at the offending
+    /// part of the message, if known.
+    pub fn diagnostic(&self, message: &str) -> String {
+        let mut out = format!("error: {}\n", self);
+        let (line, range) = match self.locate(message) {
+            Some(location) => location,
+            None => return out,
+        };
+
+        let text = message.lines().nth(line).unwrap_or_default();
+        let number = (line + 1).to_string();
+        let pad = " ".repeat(number.len());
+        let start = text.get(..range.start).unwrap_or(text).chars().count();
+        let width = text.get(range).map_or(0, |s| s.chars().count()).max(1);
+
+        out.push_str(&format!("{} |\n", pad));
+        out.push_str(&format!("{} | {}\n", number, text));
+        out.push_str(&format!(
+            "{} | {}{}\n",
+            pad,
+            " ".repeat(start),
+            "^".repeat(width)
+        ));
+
+        out
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::NonConventional(err) => write!(f, "not a conventional commit: {}", err),
+            Problem::UnknownType(type_) => write!(f, "unknown type `{}`", type_),
+            Problem::UnknownScope(scope) => write!(f, "unknown scope `{}`", scope),
+            Problem::InvalidContributorFooter(token) => {
+                write!(f, "invalid `{}` footer, expected `Name <email>`", token)
+            }
+        }
+    }
+}
+
+/// Find all problems in a commit message, using the same parsing as the
+/// change log, and the rules of the configuration.
+pub fn lint(message: &str, config: &Config) -> Vec<Problem> {
+    let (message, _) = MergeRequest::strip_prefix(message);
+    let commit = match CCommit::new(message) {
+        Ok(commit) => commit,
+        Err(err) => return vec![Problem::NonConventional(err)],
+    };
+
+    let mut problems = vec![];
+    let type_ = commit.type_();
+    let known_type = match &config.accept_types {
+        Some(types) => types.iter().any(|t| t == type_),
+        None => config.type_headers.contains_key(type_),
+    };
+    if !known_type {
+        problems.push(Problem::UnknownType(type_.to_owned()));
+    }
+
+    if let (Some(scope), Some(scopes)) = (commit.scope(), &config.scopes) {
+        if !scopes.iter().any(|s| s == scope) {
+            problems.push(Problem::UnknownScope(scope.to_owned()));
+        }
+    }
+
+    for footer in commit.footers() {
+        let token = footer.token();
+        if config
+            .contributor_footers
+            .contains(&token.to_ascii_lowercase())
+            && !is_contributor(footer.value())
+        {
+            problems.push(Problem::InvalidContributorFooter(token.to_owned()));
+        }
+    }
+
+    problems
+}
+
+/// Whether the message is skipped when linting, because it is empty (which Git
+/// rejects by itself), or because it is a fixup or default merge commit, which
+/// don't end up in the change log as-is.
+pub fn is_skipped(message: &str) -> bool {
+    let message = message.trim_start();
+
+    message.is_empty()
+        || ["fixup! ", "squash! ", "amend! ", "Merge "]
+            .iter()
+            .any(|prefix| message.starts_with(prefix))
+}
+
+/// Whether the footer value is a contributor in the `Name <email>` format.
+fn is_contributor(value: &str) -> bool {
+    value
+        .trim_end()
+        .strip_suffix('>')
+        .and_then(|value| value.rsplit_once('<'))
+        .is_some_and(|(name, email)| !name.trim().is_empty() && email.contains('@'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let config = Config {
+            scopes: Some(vec!["cli".to_owned()]),
+            ..Config::default()
+        };
+
+        #[rustfmt::skip]
+        let cases = [
+            ("feat: add feature", vec![]),
+            ("feat(cli): add feature", vec![]),
+            ("Merged PR 12: fix(cli): bug", vec![]),
+            ("feat(api): add feature", vec![Problem::UnknownScope("api".to_owned())]),
+            ("wip(api): add feature", vec![Problem::UnknownType("wip".to_owned()), Problem::UnknownScope("api".to_owned())]),
+            ("add feature", vec![Problem::NonConventional(CCommit::new("add feature").unwrap_err())]),
+            ("fix: bug\n\nCo-authored-by: Jane <jane@example.com>", vec![]),
+            ("fix: bug\n\nCo-authored-by: Jane", vec![Problem::InvalidContributorFooter("Co-authored-by".to_owned())]),
+        ];
+
+        for (message, expected) in cases {
+            assert_eq!(lint(message, &config), expected, "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_is_skipped() {
+        #[rustfmt::skip]
+        let cases = [
+            ("", true),
+            ("\n\n", true),
+            ("Merge branch 'topic'", true),
+            ("Merge pull request #1 from user/topic", true),
+            ("fixup! feat: add feature", true),
+            ("squash! feat: add feature", true),
+            ("amend! feat: add feature", true),
+            ("feat: add feature", false),
+            ("Merged PR 12: fix(cli): bug", false),
+            ("add feature", false),
+        ];
+
+        for (message, expected) in cases {
+            assert_eq!(is_skipped(message), expected, "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        let config = Config {
+            scopes: Some(vec!["cli".to_owned()]),
+            ..Config::default()
+        };
+
+        #[rustfmt::skip]
+        let cases = [
+            ("wip: add feature", "error: unknown type `wip`\n  |\n1 | wip: add feature\n  | ^^^\n"),
+            ("feat(api): add feature", "error: unknown scope `api`\n  |\n1 | feat(api): add feature\n  |      ^^^\n"),
+            ("feat add feature", "error: not a conventional commit: invalid commit format\n  |\n1 | feat add feature\n  |                 ^\n"),
+            ("fix: bug\n\nSigned-off-by: Jane", "error: invalid `Signed-off-by` footer, expected `Name <email>`\n  |\n3 | Signed-off-by: Jane\n  | ^^^^^^^^^^^^^^^^^^^\n"),
+        ];
+
+        for (message, expected) in cases {
+            let problems = lint(message, &config);
+            assert_eq!(problems[0].diagnostic(message), expected, "{}", message);
+        }
+    }
+}
@@ -1,15 +1,37 @@
-use std::{convert::TryInto as _, env, io::Write as _, process::Command};
+use std::{convert::TryInto as _, env, io::Write as _, path::Path, process::Command};
 
 use jaq_core::load;
 use jilu::{
-    changelog::Change,
-    git::{self, Tag},
+    changelog::{Bump, Change, Range, FORMATS},
+    config::HistoryMode,
+    git::{self, Mailmap, Tag},
+    lint,
+    note::Note,
     Changelog, Config, Error,
 };
 use semver::Version;
 use serde_json::Value;
 
 fn main() {
+    let command = match env::args().nth(1).as_deref() {
+        Some("note") => NoteOpts::parse().and_then(note),
+        Some("check") => CheckOpts::parse().and_then(check),
+        Some("lint-message") => LintMessageOpts::parse().and_then(lint_message),
+        Some("install-hook") => InstallHookOpts::parse().and_then(install_hook),
+        _ => return changelog(),
+    };
+
+    match command {
+        Ok(out) => print!("{}", out),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Generate the change log.
+fn changelog() {
     let opts = match Opts::parse() {
         Ok(opts) => opts,
         Err(err) => {
@@ -48,6 +70,12 @@ struct Opts {
     /// The change log file. Defaults to `CHANGELOG.md`.
     file: String,
 
+    /// The configuration file, overriding any discovered configuration.
+    config: Option<String>,
+
+    /// The change log template file, overriding the configured template.
+    template: Option<String>,
+
     /// If set, the change log will be written to the file instead of printed to
     /// `stdout`.
     write: bool,
@@ -55,8 +83,9 @@ struct Opts {
     /// If set, the change log will be rendered without inline configuration.
     strip_config: bool,
 
-    /// Output the release notes either in `text` or `json` format. Defaults to
-    /// `text`, unless `write` is set, in which case it defaults to `none`.
+    /// Output the release notes either in `text` or `json` format, or the
+    /// releases as an `atom` or `rss` feed. Defaults to `text`, unless `write`
+    /// is set, in which case it defaults to `none`.
     output: Option<String>,
 
     /// If set, the output will be written to the file instead of printed to
@@ -64,6 +93,10 @@ struct Opts {
     output_file: Option<String>,
 
     /// Version to use for the unreleased changes.
+    ///
+    /// This is either an explicit version, `major`, `minor` or `patch` to bump
+    /// the latest released version, or `auto` to determine the bump based on
+    /// the unreleased changes.
     release: Option<String>,
 
     /// Optional release notes for the unreleased changes.
@@ -72,8 +105,33 @@ struct Opts {
     /// Edit the release notes in `$EDITOR`.
     edit_release_notes: bool,
 
+    /// If set, the change log is written and committed, and an annotated Git
+    /// tag is created for the release. Requires `release` to be set.
+    tag: bool,
+
+    /// Sign the created Git tag.
+    sign: bool,
+
     /// Optional `jq` query filter to apply to the JSON output.
     jq: Option<String>,
+
+    /// The way the Git history is walked, overriding the configured mode.
+    history: Option<HistoryMode>,
+
+    /// Only include commits after this revision.
+    from: Option<String>,
+
+    /// Only include commits up to and including this revision. Defaults to
+    /// `HEAD`.
+    to: Option<String>,
+
+    /// Only output a single release, identified by its version, or
+    /// `unreleased` for the unreleased changes.
+    only: Option<String>,
+
+    /// The name of the built-in template used to render the change log in a
+    /// format other than Markdown (e.g. `debian` or `rpm`).
+    format: Option<&'static str>,
 }
 
 impl Opts {
@@ -85,10 +143,19 @@ impl Opts {
         let mut output = None;
         let mut output_file = None;
         let mut file = None;
+        let mut config = None;
+        let mut template = None;
         let mut jq = None;
         let mut release = None;
         let mut release_notes = None;
         let mut edit_release_notes = false;
+        let mut tag = false;
+        let mut sign = false;
+        let mut history = None;
+        let mut from = None;
+        let mut to = None;
+        let mut only = None;
+        let mut format = None;
 
         let mut parser = lexopt::Parser::from_env();
         while let Some(arg) = parser.next()? {
@@ -100,6 +167,8 @@ impl Opts {
                     output = match parser.value()?.parse()? {
                         v if v == "text" => Some(v),
                         v if v == "json" => Some(v),
+                        v if v == "atom" => Some(v),
+                        v if v == "rss" => Some(v),
                         v if v == "none" => Some(v),
                         _ => None,
                     };
@@ -119,11 +188,43 @@ impl Opts {
                 Short('e') | Long("edit") => {
                     edit_release_notes = true;
                 }
+                Short('t') | Long("tag") => {
+                    tag = true;
+                }
+                Short('s') | Long("sign") => {
+                    sign = true;
+                }
+                Long("history") => {
+                    history = Some(parser.value()?.parse()?);
+                }
+                Long("from") => {
+                    from = Some(parser.value()?.string()?);
+                }
+                Long("to") => {
+                    to = Some(parser.value()?.string()?);
+                }
+                Long("only") => {
+                    only = Some(parser.value()?.string()?);
+                }
+                Long("format") => {
+                    let name = parser.value()?.string()?;
+                    format = match FORMATS.iter().find(|(format, _)| *format == name) {
+                        Some((_, template)) => Some(*template),
+                        None if name == "markdown" => None,
+                        None => Err(lexopt::Error::from(format!("Unknown format: {}", name)))?,
+                    };
+                }
+                Short('c') | Long("config") => {
+                    config = Some(parser.value()?.string()?);
+                }
+                Long("template") => {
+                    template = Some(parser.value()?.string()?);
+                }
                 Long("strip-config") => {
                     strip_config = true;
                 }
                 Short('h') | Long("help") => {
-                    println!("Usage: jilu [-r|--release=VERSION] [-n|--notes=RELEASE_NOTES] [-e|--edit] [-w|--write] [CHANGELOG]");
+                    println!("Usage: jilu [-r|--release=VERSION|auto|major|minor|patch] [-n|--notes=RELEASE_NOTES] [-e|--edit] [-w|--write] [-t|--tag [-s|--sign]] [--history=first-parent|full|merge-expanded] [--from=REV] [--to=REV] [--only=VERSION|unreleased] [--format=markdown|debian|rpm] [-c|--config=FILE] [--template=FILE] [CHANGELOG]");
                     std::process::exit(0);
                 }
                 Value(v) if file.is_none() => {
@@ -133,12 +234,35 @@ impl Opts {
             }
         }
 
+        let tag = tag || env::var("RELEASE_TAG").is_ok();
+        let write = write || tag || env::var("WRITE_CHANGELOG").is_ok();
+
+        // Writing a different format requires an explicit destination, to
+        // avoid overwriting the Markdown change log.
+        if format.is_some() && write && file.is_none() {
+            Err(lexopt::Error::from(
+                "Using --format together with --write requires a CHANGELOG file argument.",
+            ))?;
+        }
+
         let file = file
             .or_else(|| env::var("CHANGELOG").ok())
             .unwrap_or_else(|| "CHANGELOG.md".to_owned());
-
-        let write = write || env::var("WRITE_CHANGELOG").is_ok();
         let release = release.or_else(|| env::var("RELEASE").ok());
+        if format.is_some() && only.is_some() {
+            Err(lexopt::Error::from(
+                "Using --format together with --only is not supported.",
+            ))?;
+        }
+
+        if tag && release.is_none() {
+            Err(lexopt::Error::from("Using --tag requires --release."))?;
+        } else if tag && to.is_some() {
+            Err(lexopt::Error::from(
+                "Using --tag together with --to is not supported.",
+            ))?;
+        }
+
         let release_notes = release_notes.or_else(|| env::var("RELEASE_NOTES").ok());
         let edit_release_notes = edit_release_notes || env::var("RELEASE_EDIT").is_ok();
         if !write && output.is_none() {
@@ -151,6 +275,8 @@ impl Opts {
 
         Ok(Self {
             file,
+            config,
+            template,
             write,
             strip_config,
             output,
@@ -158,41 +284,491 @@ impl Opts {
             release,
             release_notes,
             edit_release_notes,
+            tag,
+            sign,
             jq,
+            history,
+            from,
+            to,
+            only,
+            format,
+        })
+    }
+}
+
+/// The options of the `jilu note` command.
+struct NoteOpts {
+    /// The change log file, used to find the inline configuration.
+    file: String,
+
+    /// The configuration file, overriding any discovered configuration.
+    config: Option<String>,
+
+    /// The revision to attach the note to. Defaults to `HEAD`.
+    rev: String,
+
+    /// The change log override to write.
+    note: Note,
+}
+
+impl NoteOpts {
+    fn parse() -> Result<Self, Error> {
+        use lexopt::{Arg::*, ValueExt as _};
+
+        let mut config = None;
+        let mut rev = None;
+        let mut note = Note::default();
+
+        let mut parser = lexopt::Parser::from_args(env::args_os().skip(2));
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Short('m') | Long("message") => {
+                    note.message = Some(parser.value()?.string()?);
+                }
+                Long("type") => {
+                    note.type_ = Some(parser.value()?.string()?);
+                }
+                Long("scope") => {
+                    note.scope = Some(parser.value()?.string()?);
+                }
+                Short('d') | Long("description") => {
+                    note.description = Some(parser.value()?.string()?);
+                }
+                Short('b') | Long("body") => {
+                    note.body = Some(parser.value()?.string()?.replace("\\n", "\n"));
+                }
+                Long("hide") => {
+                    note.hidden = true;
+                }
+                Short('c') | Long("config") => {
+                    config = Some(parser.value()?.string()?);
+                }
+                Short('h') | Long("help") => {
+                    println!("Usage: jilu note [-m|--message=MESSAGE] [--type=TYPE] [--scope=SCOPE] [-d|--description=DESCRIPTION] [-b|--body=BODY] [--hide] [-c|--config=FILE] [REV]");
+                    std::process::exit(0);
+                }
+                Value(v) if rev.is_none() => {
+                    rev = Some(v.string()?);
+                }
+                _ => return Err(arg.unexpected().into()),
+            }
+        }
+
+        if note == Note::default() {
+            Err(lexopt::Error::from(
+                "Nothing to write, use --message, --type, --scope, --description, --body or --hide.",
+            ))?;
+        }
+
+        Ok(Self {
+            file: env::var("CHANGELOG").unwrap_or_else(|_| "CHANGELOG.md".to_owned()),
+            config,
+            rev: rev.unwrap_or_else(|| "HEAD".to_owned()),
+            note,
+        })
+    }
+}
+
+/// The options of the `jilu check` command.
+struct CheckOpts {
+    /// The change log file, used to find the inline configuration.
+    file: String,
+
+    /// The configuration file, overriding any discovered configuration.
+    config: Option<String>,
+
+    /// Only check commits after this revision. Defaults to the latest release.
+    since: Option<String>,
+}
+
+impl CheckOpts {
+    fn parse() -> Result<Self, Error> {
+        use lexopt::{Arg::*, ValueExt as _};
+
+        let mut config = None;
+        let mut since = None;
+
+        let mut parser = lexopt::Parser::from_args(env::args_os().skip(2));
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("since") => {
+                    since = Some(parser.value()?.string()?);
+                }
+                Short('c') | Long("config") => {
+                    config = Some(parser.value()?.string()?);
+                }
+                Short('h') | Long("help") => {
+                    println!("Usage: jilu check [--since=REV] [-c|--config=FILE]");
+                    std::process::exit(0);
+                }
+                _ => return Err(arg.unexpected().into()),
+            }
+        }
+
+        Ok(Self {
+            file: env::var("CHANGELOG").unwrap_or_else(|_| "CHANGELOG.md".to_owned()),
+            config,
+            since,
+        })
+    }
+}
+
+/// Check the commit messages since the latest release (or the given revision)
+/// against the conventions of the change log, reporting every problem.
+fn check(opts: CheckOpts) -> Result<String, Error> {
+    let repo = git2::Repository::open(".")?;
+    let config =
+        Config::from_environment(&repo, &opts.file, opts.config.as_deref().map(Path::new))?;
+
+    let mut ignored = vec![];
+    let mut commits = git::commits(&repo, opts.since.as_deref(), "HEAD", &config, &mut ignored)?;
+    if opts.since.is_none() {
+        let tags = git::tags(&repo, &commits, &config.tag_pattern, &mut ignored)?;
+        let latest = tags
+            .iter()
+            .max_by(|a, b| a.version.cmp(&b.version))
+            .and_then(|tag| commits.iter().position(|c| c.id == tag.commit.id));
+
+        if let Some(idx) = latest {
+            commits.drain(..=idx);
+        }
+    }
+
+    let mut invalid = 0;
+    for commit in &commits {
+        // In the `merge-expanded` history mode, the commits merged by a merge
+        // commit are checked instead of the merge commit itself.
+        let checked = if commit.merged.is_empty() {
+            vec![commit]
+        } else {
+            commit.merged.iter().collect()
+        };
+
+        for commit in checked {
+            if lint::is_skipped(&commit.message) {
+                continue;
+            }
+
+            let problems = lint::lint(&commit.message, &config);
+            if problems.is_empty() {
+                continue;
+            }
+
+            invalid += 1;
+            let subject = commit.message.lines().next().unwrap_or_default();
+            println!("{} {}", commit.short_id, subject);
+            for problem in problems {
+                println!("  - {}", problem);
+            }
+        }
+    }
+
+    if invalid > 0 {
+        return Err(Error::InvalidCommits(invalid));
+    }
+
+    Ok(String::new())
+}
+
+/// The options of the `jilu lint-message` command.
+struct LintMessageOpts {
+    /// The change log file, used to find the inline configuration.
+    file: String,
+
+    /// The configuration file, overriding any discovered configuration.
+    config: Option<String>,
+
+    /// The file containing the commit message, or `-` for `stdin`.
+    message: String,
+}
+
+impl LintMessageOpts {
+    fn parse() -> Result<Self, Error> {
+        use lexopt::{Arg::*, ValueExt as _};
+
+        let mut config = None;
+        let mut message = None;
+
+        let mut parser = lexopt::Parser::from_args(env::args_os().skip(2));
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Short('c') | Long("config") => {
+                    config = Some(parser.value()?.string()?);
+                }
+                Short('h') | Long("help") => {
+                    println!("Usage: jilu lint-message [-c|--config=FILE] <FILE|->");
+                    std::process::exit(0);
+                }
+                Value(v) if message.is_none() => {
+                    message = Some(v.string()?);
+                }
+                _ => return Err(arg.unexpected().into()),
+            }
+        }
+
+        Ok(Self {
+            file: env::var("CHANGELOG").unwrap_or_else(|_| "CHANGELOG.md".to_owned()),
+            config,
+            message: message.ok_or(lexopt::Error::from("Missing commit message file."))?,
         })
     }
 }
 
+/// Check a single commit message (e.g. from a `commit-msg` hook) against the
+/// conventions of the change log, printing a diagnostic for every problem.
+fn lint_message(opts: LintMessageOpts) -> Result<String, Error> {
+    let repo = git2::Repository::open(".")?;
+    let config =
+        Config::from_environment(&repo, &opts.file, opts.config.as_deref().map(Path::new))?;
+
+    let text = match opts.message.as_str() {
+        "-" => std::io::read_to_string(std::io::stdin())?,
+        path => std::fs::read_to_string(path)?,
+    };
+
+    // Git passes the message to the hook before removing comments, and
+    // everything below the scissors line of `git commit --verbose`.
+    let message = text
+        .lines()
+        .take_while(|line| !line.starts_with("# ------------------------ >8"))
+        .filter(|line| !line.starts_with('#'))
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = message.trim();
+
+    if lint::is_skipped(message) {
+        return Ok(String::new());
+    }
+
+    let problems = lint::lint(message, &config);
+    for problem in &problems {
+        eprintln!("{}", problem.diagnostic(message));
+    }
+
+    if !problems.is_empty() {
+        return Err(Error::InvalidCommits(1));
+    }
+
+    Ok(String::new())
+}
+
+/// The options of the `jilu install-hook` command.
+struct InstallHookOpts {
+    /// Overwrite an existing `commit-msg` hook.
+    force: bool,
+}
+
+impl InstallHookOpts {
+    fn parse() -> Result<Self, Error> {
+        use lexopt::Arg::*;
+
+        let mut force = false;
+
+        let mut parser = lexopt::Parser::from_args(env::args_os().skip(2));
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Short('f') | Long("force") => {
+                    force = true;
+                }
+                Short('h') | Long("help") => {
+                    println!("Usage: jilu install-hook [-f|--force]");
+                    std::process::exit(0);
+                }
+                _ => return Err(arg.unexpected().into()),
+            }
+        }
+
+        Ok(Self { force })
+    }
+}
+
+/// Install `jilu lint-message` as the `commit-msg` hook of the repository.
+fn install_hook(opts: InstallHookOpts) -> Result<String, Error> {
+    let repo = git2::Repository::open(".")?;
+    let dir = match repo.config()?.get_path("core.hooksPath") {
+        Ok(dir) if dir.is_relative() => repo.workdir().unwrap_or(repo.path()).join(dir),
+        Ok(dir) => dir,
+        Err(_) => repo.path().join("hooks"),
+    };
+
+    let path = dir.join("commit-msg");
+    if path.exists() && !opts.force {
+        return Err(format!(
+            "Hook {} already exists, use --force to overwrite it.",
+            path.display()
+        )
+        .into());
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, "#!/bin/sh\nexec jilu lint-message \"$1\"\n")?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    eprintln!("Wrote {}", path.display());
+
+    Ok(String::new())
+}
+
+/// Attach a change log note to a commit, in the configured notes reference.
+fn note(opts: NoteOpts) -> Result<String, Error> {
+    let repo = git2::Repository::open(".")?;
+    let config =
+        Config::from_environment(&repo, &opts.file, opts.config.as_deref().map(Path::new))?;
+    let notes_ref = config
+        .notes_ref
+        .as_deref()
+        .ok_or("Writing notes requires `notes_ref` to be configured.")?;
+
+    let id = git::write_note(&repo, notes_ref, &opts.rev, &opts.note)?;
+    eprintln!("Wrote note for {} to {}", id, notes_ref);
+
+    Ok(String::new())
+}
+
 fn run(opts: Opts) -> Result<String, Error> {
     let repo = git2::Repository::open(".")?;
-    let config = Config::from_environment(&repo, &opts.file)?;
-    let commits = git::commits(&repo, config.root_commit.as_deref(), &config.ignore_commits)?;
-    let mut tags = git::tags(&repo, &commits)?;
+    let mut config =
+        Config::from_environment(&repo, &opts.file, opts.config.as_deref().map(Path::new))?;
+    if let Some(history) = opts.history {
+        config.history_mode = history;
+    }
+    if let Some(template) = opts.template.as_deref() {
+        config.template_path = Some(template.into());
+    }
+
+    let to = opts.to.as_deref().unwrap_or("HEAD");
+    let mailmap = Mailmap::new(&repo, &config.mailmap)?;
+    let mut ignored = vec![];
+    let commits = git::commits(&repo, opts.from.as_deref(), to, &config, &mut ignored)?;
+    let mut tags = git::tags(&repo, &commits, &config.tag_pattern, &mut ignored)?;
+
+    // The change log starts after the tag of `--from`, if any, and includes
+    // the commits before it only to find contributors.
+    let (from, earlier) = match opts.from.as_deref() {
+        Some(rev) => (
+            Some(git::tag_at(&repo, rev, &config.tag_pattern)?.unwrap_or_else(|| rev.to_owned())),
+            git::commits(&repo, None, rev, &config, &mut vec![])?,
+        ),
+        None => (None, vec![]),
+    };
+    let last: git::Commit = repo.revparse_single(to)?.peel_to_commit()?.try_into()?;
+    let range = Range {
+        commits: &commits,
+        earlier: &earlier,
+        from: from.as_deref(),
+        to,
+        date: last.time,
+    };
+    let mut release_tag = None;
+
+    if opts.tag && git::is_dirty(&repo)? {
+        return Err(Error::DirtyWorkingTree);
+    }
+
+    if let Some(release) = opts.release {
+        let log = Changelog::new(
+            &config,
+            Some(&mailmap),
+            range,
+            tags.clone(),
+            ignored.clone(),
+        )?;
+        let version = match release.as_str() {
+            "auto" => log.next_version(None)?,
+            "major" | "minor" | "patch" => log.next_version(Some(release.parse::<Bump>()?))?,
+            v => Version::parse(v.strip_prefix('v').unwrap_or(v))?,
+        };
+
+        let name = config.tag_pattern.name(&version);
+        if opts.tag && git::tag_exists(&repo, &name) {
+            return Err(Error::TagExists(name));
+        }
 
-    if let Some(version) = opts.release {
-        let log = Changelog::new(&config, &commits, tags.clone())?;
         let tag = tag_unreleased(
             &repo,
+            to,
+            name,
             version,
             opts.release_notes,
             opts.edit_release_notes,
             log.unreleased().changes(),
         )?;
+        if opts.tag {
+            release_tag = Some(tag.clone());
+        }
+
         tags.push(tag);
         tags.sort_by(|a, b| a.version.cmp(&b.version));
     }
 
-    let log = Changelog::new(&config, &commits, tags)?;
+    let log = Changelog::new(&config, Some(&mailmap), range, tags, ignored)?;
+    let (format, strip_config) = (opts.format, opts.strip_config);
+    let render = || match format {
+        None => log.render(!strip_config),
+        Some(template) => log.render_template(template, None),
+    };
 
+    let mut written = vec![];
     if opts.write {
-        std::fs::write(&opts.file, log.render(!opts.strip_config)?)?;
+        // Render all files before writing any of them, to avoid writing only
+        // some of the files if a template fails to render.
+        let mut files = vec![(Path::new(&opts.file), render()?)];
+        for output in &config.outputs {
+            files.push((output.destination.as_path(), log.render_output(output)?));
+        }
+
+        for (path, contents) in files {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)?;
+            }
+
+            std::fs::write(path, contents)?;
+            eprintln!("Wrote {}", path.display());
+            written.push(path);
+        }
+    }
+
+    if let Some(tag) = release_tag {
+        let message = tag.message.unwrap_or_default();
+        let release = git::release_commit_message(&tag.name);
+        let commit = git::commit_files(&repo, &written, &release)?;
+
+        let sign = opts.sign
+            || repo
+                .config()
+                .and_then(|c| c.get_bool("tag.gpgSign"))
+                .unwrap_or(false);
+
+        git::create_tag(&repo, &tag.name, &commit, &message, sign)?;
     }
 
+    let only = match opts.only.as_deref() {
+        None => None,
+        Some("unreleased") => Some(None),
+        Some(v) => Some(Some(Version::parse(v.strip_prefix('v').unwrap_or(v))?)),
+    };
+
+    let json = || match &only {
+        None => serde_json::to_value(&log).map_err(Error::from),
+        Some(version) => log.release(version.as_ref()),
+    };
+
     match (opts.output.as_deref(), opts.jq.as_deref()) {
-        (Some("text"), _) => Ok(log.render(!opts.strip_config)?),
-        (Some("json"), None) => Ok(serde_json::to_string(&log)?),
+        (Some("text"), _) => match &only {
+            None => render(),
+            Some(version) => log.render_release(version.as_ref()),
+        },
+        (Some("atom"), _) => log.render_template("jilu/atom", None),
+        (Some("rss"), _) => log.render_template("jilu/rss", None),
+        (Some("json"), None) => Ok(serde_json::to_string(&json()?)?),
         (Some("json"), Some(code)) => {
-            let json = serde_json::to_value(&log)?;
+            let json = json()?;
             let program = load::File { code, path: () };
             let loader = load::Loader::new(jaq_std::defs().chain(jaq_json::defs()));
             let arena = load::Arena::default();
@@ -240,23 +816,24 @@ fn run(opts: Opts) -> Result<String, Error> {
 /// notes as the one used for the unreleased commits.
 fn tag_unreleased(
     repo: &git2::Repository,
-    version: String,
+    rev: &str,
+    name: String,
+    version: Version,
     notes: Option<String>,
     edit: bool,
     changes: &[Change],
 ) -> Result<Tag, Error> {
-    let version = Version::parse(version.strip_prefix('v').unwrap_or(&version))?;
     let notes = notes.map(|v| v.replace("\\n", "\n"));
     let mut instructions = format!(
         r#"
 #
 # Write a message for release:
-#   v{}
+#   {}
 #
 # - The first line is the release title.
 # - Subsequent lines are the release notes.
 # - This comment will be stripped from the release notes."#,
-        version
+        name
     );
 
     if !changes.is_empty() {
@@ -283,12 +860,14 @@ fn tag_unreleased(
             .to_owned();
     }
 
+    // Use the message as stored by Git, so the change log rendered using this
+    // tag matches the one rendered after the tag is created.
     Ok(Tag {
-        name: format!("v{}", version),
-        message: Some(message),
+        name,
+        message: Some(git::tag_message(&message)),
         version,
         tagger: repo.signature()?.try_into().ok(),
-        commit: repo.head()?.peel_to_commit()?.try_into()?,
+        commit: repo.revparse_single(rev)?.peel_to_commit()?.try_into()?,
     })
 }
 
@@ -0,0 +1,153 @@
+use crate::Error;
+use conventional::{Commit as CCommit, Simple as _};
+use ron::extensions::Extensions;
+use serde::{Deserialize, Serialize};
+
+/// A change log override attached to a commit as a Git note.
+///
+/// Notes are written in RON (e.g. `(scope: "cli", hidden: false)`). A note
+/// that is not valid RON replaces the full commit message instead, which
+/// allows using `git notes add -m "fix: corrected description"` directly.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Note {
+    /// The conventional commit message replacing the original message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// The type replacing the type of the change.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+
+    /// The scope replacing the scope of the change, or no scope if empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// The description replacing the description of the change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The body replacing the body of the change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+
+    /// Hide the change from the change log.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+}
+
+impl Note {
+    /// Parse the contents of a Git note.
+    pub fn parse(note: &str) -> Self {
+        ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(note)
+            .unwrap_or_else(|_| Self {
+                message: Some(note.trim().to_owned()),
+                ..Self::default()
+            })
+    }
+
+    /// Apply the note to the given commit message.
+    ///
+    /// If the resulting message is not a conventional commit (e.g. because
+    /// the original message is not, and the note does not set a type), the
+    /// message is returned without applying the type, scope or description.
+    pub fn apply(&self, message: &str) -> String {
+        let message = self.message.as_deref().unwrap_or(message);
+        if self.type_.is_none()
+            && self.scope.is_none()
+            && self.description.is_none()
+            && self.body.is_none()
+        {
+            return message.to_owned();
+        }
+
+        let (subject, rest) = message.split_once('\n').unwrap_or((message, ""));
+        let commit = CCommit::new(message).ok();
+        let type_ = self.type_.as_deref().or(commit.as_ref().map(|c| c.type_()));
+        let description = (self.description.as_deref())
+            .or(commit.as_ref().map(|c| c.description()))
+            .unwrap_or(subject);
+        let body = (self.body.as_deref())
+            .or(commit.as_ref().map_or(Some(rest.trim()), |c| c.body()))
+            .filter(|body| !body.is_empty());
+
+        let mut new = match type_ {
+            Some(type_) => {
+                let scope = match self.scope.as_deref() {
+                    Some(scope) => Some(scope).filter(|s| !s.is_empty()),
+                    None => commit.as_ref().and_then(|c| c.scope()),
+                };
+                let breaking = commit.as_ref().is_some_and(|c| {
+                    c.breaking()
+                        && !c
+                            .footers()
+                            .iter()
+                            .any(|f| matches!(f.token(), "BREAKING CHANGE" | "BREAKING-CHANGE"))
+                });
+
+                format!(
+                    "{}{}{}: {}",
+                    type_,
+                    scope.map(|s| format!("({})", s)).unwrap_or_default(),
+                    if breaking { "!" } else { "" },
+                    description
+                )
+            }
+            None => description.to_owned(),
+        };
+
+        if let Some(body) = body {
+            new.push_str("\n\n");
+            new.push_str(body);
+        }
+
+        let footers = commit.as_ref().map(|c| c.footers()).unwrap_or_default();
+        if !footers.is_empty() {
+            new.push('\n');
+        }
+        for footer in footers {
+            new.push_str(&format!(
+                "\n{}{}{}",
+                footer.token(),
+                footer.separator(),
+                footer.value()
+            ));
+        }
+
+        new
+    }
+
+    /// The contents of the note, as written to Git.
+    pub fn to_ron(&self) -> Result<String, Error> {
+        let config = ron::ser::PrettyConfig::default().struct_names(false);
+        let options = ron::Options::default().with_default_extension(Extensions::IMPLICIT_SOME);
+
+        Ok(options.to_string_pretty(self, config)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        #[rustfmt::skip]
+        let cases = [
+            ("fix: typo", "feat: corrected", "feat: corrected"),
+            ("fix: tpyo", r#"(description: "typo")"#, "fix: typo"),
+            ("fix(cli)!: typo", r#"(type: "feat")"#, "feat(cli)!: typo"),
+            ("fix(cli): typo", r#"(scope: "")"#, "fix: typo"),
+            ("fix: typo\n\nCloses #1", r#"(scope: "api", body: "More details.")"#, "fix(api): typo\n\nMore details.\n\nCloses #1"),
+            ("Update readme", r#"(type: "docs")"#, "docs: Update readme"),
+            ("Update readme", r#"(scope: "api")"#, "Update readme"),
+            ("fix: typo", "(hidden: true)", "fix: typo"),
+        ];
+
+        for (message, note, expected) in cases {
+            assert_eq!(Note::parse(note).apply(message), expected, "{}", note);
+        }
+    }
+}
@@ -1,4 +1,7 @@
 use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use tera::{to_value, try_get_value, Filter, Result, Value};
 
 pub(crate) struct TypeHeader(pub(crate) HashMap<String, String>);
@@ -39,3 +42,41 @@ pub(crate) fn indent(value: &Value, args: &HashMap<String, Value>) -> Result<Val
 
     Ok(to_value(out.join("\n"))?)
 }
+
+/// Render Markdown to HTML.
+pub(crate) fn markdown(value: &Value, _args: &HashMap<String, Value>) -> Result<Value> {
+    let s = try_get_value!("markdown", "value", String, value);
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, pulldown_cmark::Parser::new(&s));
+
+    Ok(to_value(html)?)
+}
+
+/// Find all template files in the given directory and its subdirectories,
+/// named by their path relative to the directory (e.g. `partials/change.md`).
+pub(crate) fn template_files(dir: &Path) -> io::Result<Vec<(PathBuf, Option<String>)>> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(current) = dirs.pop() {
+        for entry in fs::read_dir(current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if let Ok(name) = path.strip_prefix(dir) {
+                let name = template_name(name);
+                files.push((path, Some(name)));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// The name of a template file, using forward slashes on all platforms.
+pub(crate) fn template_name(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}